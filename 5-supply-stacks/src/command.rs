@@ -1,5 +1,7 @@
 use anyhow::{anyhow, Result};
-use std::io::BufRead;
+use std::{fmt, io::BufRead};
+
+use crate::crane::{Crane, MoveStats};
 
 use nom::{
     branch::alt,
@@ -12,18 +14,88 @@ use nom::{
 
 #[derive(Clone, Copy)]
 pub struct CraneMove {
+    index: usize,
     count: usize,
     source: usize,
     target: usize,
 }
 
-pub enum CraneVersion {
-    CraneMover9000,
-    CraneMover9001,
+#[derive(Debug)]
+pub enum InvalidMove {
+    NoSuchStack {
+        index: usize,
+        source: usize,
+        target: usize,
+        stacks: usize,
+    },
+    NotEnoughCrates {
+        index: usize,
+        source: usize,
+        target: usize,
+        count: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidMove::NoSuchStack {
+                index,
+                source,
+                target,
+                stacks,
+            } => write!(
+                f,
+                "command #{} (from {} to {}) refers to a stack that does not exist, there are {} stacks",
+                index + 1,
+                source,
+                target,
+                stacks
+            ),
+            InvalidMove::NotEnoughCrates {
+                index,
+                source,
+                target,
+                count,
+                height,
+            } => write!(
+                f,
+                "command #{} (from {} to {}) wants to move {} crates, but stack {} has only {}",
+                index + 1,
+                source,
+                target,
+                count,
+                source,
+                height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidMove {}
+
+impl fmt::Display for CraneMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count, self.source, self.target
+        )
+    }
 }
 
 impl CraneMove {
-    fn parse(line: &str) -> IResult<&str, Self> {
+    pub fn new(index: usize, count: usize, source: usize, target: usize) -> Self {
+        Self {
+            index,
+            count,
+            source,
+            target,
+        }
+    }
+
+    fn parse(line: &str, index: usize) -> IResult<&str, Self> {
         map(
             terminated(
                 tuple((
@@ -40,6 +112,7 @@ impl CraneMove {
                 alt((line_ending, eof)),
             ),
             |(count, source, target)| Self {
+                index,
                 count,
                 source,
                 target,
@@ -47,37 +120,80 @@ impl CraneMove {
         )(line)
     }
 
-    pub fn execute(&self, stacks: &mut [Vec<char>], version: CraneVersion) {
+    pub fn execute(
+        &self,
+        stacks: &mut [Vec<String>],
+        crane: &dyn Crane,
+    ) -> Result<MoveStats, InvalidMove> {
         let Self {
+            index,
             count,
             source,
             target,
-        } = self;
-
-        let split_at = stacks[*source - 1].len() - count;
-        let to_move = stacks[*source - 1].split_off(split_at);
-        match version {
-            CraneVersion::CraneMover9000 => {
-                stacks[*target - 1].extend(to_move.into_iter().rev());
-            }
-            CraneVersion::CraneMover9001 => {
-                stacks[*target - 1].extend(to_move);
-            }
+        } = *self;
+
+        let in_range = |stack: usize| (1..=stacks.len()).contains(&stack);
+        if !in_range(source) || !in_range(target) {
+            return Err(InvalidMove::NoSuchStack {
+                index,
+                source,
+                target,
+                stacks: stacks.len(),
+            });
+        }
+
+        let height = stacks[source - 1].len();
+        if count > height {
+            return Err(InvalidMove::NotEnoughCrates {
+                index,
+                source,
+                target,
+                count,
+                height,
+            });
+        }
+
+        let mut stats = MoveStats::default();
+        for lift in crane.lifts(count) {
+            let split_at = stacks[source - 1].len() - lift;
+            let lifted = stacks[source - 1].split_off(split_at);
+            stacks[target - 1].extend(lifted);
+
+            stats.lifts += 1;
+            stats.crates += lift;
         }
+
+        debug_assert_eq!(stats.crates, count, "{} lifted wrong amount", crane.name());
+        Ok(stats)
+    }
+
+    /// Crates this move takes from the source stack, bottom first. `None` if the move is invalid.
+    pub fn taken<'s>(&self, stacks: &'s [Vec<String>]) -> Option<&'s [String]> {
+        let stack = stacks.get(self.source.checked_sub(1)?)?;
+        stack.get(stack.len().checked_sub(self.count)?..)
+    }
+
+    /// Reverts this move, given crates it took from the source stack.
+    pub fn undo(&self, stacks: &mut [Vec<String>], taken: Vec<String>) {
+        let target = &mut stacks[self.target - 1];
+        target.truncate(target.len() - taken.len());
+        stacks[self.source - 1].extend(taken);
     }
 }
 
 pub fn read_all(mut reader: impl BufRead) -> impl Iterator<Item = Result<CraneMove>> {
     let mut buf = String::new();
+    let mut index = 0;
     // Remove newline between stacks and commands.
     reader.read_line(&mut buf).ok();
 
     std::iter::from_fn(move || {
         buf.truncate(0);
+        index += 1;
         match reader.read_line(&mut buf) {
             Ok(0) => None,
             Ok(_) => Some(
-                CraneMove::parse(&buf)
+                CraneMove::parse(&buf, index - 1)
                     .map(|(_, op)| op)
                     .map_err(|e| anyhow!("failed to parse command: {}", e)),
             ),
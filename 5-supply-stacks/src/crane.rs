@@ -0,0 +1,113 @@
+use std::ops::AddAssign;
+
+/// Model of a crane. Each model decides how crates of a single move are split into lifts.
+/// Every lift takes crates from the top of the source stack and puts them on the target
+/// stack, keeping their order.
+pub trait Crane {
+    fn name(&self) -> String;
+
+    /// Sizes of consecutive lifts needed to move `count` crates. They must sum up to `count`.
+    fn lifts(&self, count: usize) -> Vec<usize>;
+}
+
+/// Moves crates one by one.
+pub struct CraneMover9000;
+
+/// Moves all crates at once.
+pub struct CraneMover9001;
+
+/// Moves as many crates as possible at once, but no more than `capacity`.
+pub struct CapacityCrane {
+    capacity: usize,
+}
+
+/// Moves crates in batches of exactly `batch` crates. Crates left over are moved one by one.
+pub struct BatchCrane {
+    batch: usize,
+}
+
+impl Crane for CraneMover9000 {
+    fn name(&self) -> String {
+        "CraneMover9000".into()
+    }
+
+    fn lifts(&self, count: usize) -> Vec<usize> {
+        vec![1; count]
+    }
+}
+
+impl Crane for CraneMover9001 {
+    fn name(&self) -> String {
+        "CraneMover9001".into()
+    }
+
+    fn lifts(&self, count: usize) -> Vec<usize> {
+        (count > 0).then_some(count).into_iter().collect()
+    }
+}
+
+impl CapacityCrane {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+        }
+    }
+}
+
+impl Crane for CapacityCrane {
+    fn name(&self) -> String {
+        format!("CapacityCrane({})", self.capacity)
+    }
+
+    fn lifts(&self, count: usize) -> Vec<usize> {
+        let mut lifts = vec![self.capacity; count / self.capacity];
+        if !count.is_multiple_of(self.capacity) {
+            lifts.push(count % self.capacity);
+        }
+
+        lifts
+    }
+}
+
+impl BatchCrane {
+    pub fn new(batch: usize) -> Self {
+        Self {
+            batch: batch.max(1),
+        }
+    }
+}
+
+impl Crane for BatchCrane {
+    fn name(&self) -> String {
+        format!("BatchCrane({})", self.batch)
+    }
+
+    fn lifts(&self, count: usize) -> Vec<usize> {
+        let mut lifts = vec![self.batch; count / self.batch];
+        lifts.extend(vec![1; count % self.batch]);
+
+        lifts
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MoveStats {
+    pub lifts: usize,
+    pub crates: usize,
+}
+
+impl AddAssign for MoveStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.lifts += rhs.lifts;
+        self.crates += rhs.crates;
+    }
+}
+
+pub fn registered() -> Vec<Box<dyn Crane>> {
+    vec![
+        Box::new(CraneMover9000),
+        Box::new(CraneMover9001),
+        Box::new(CapacityCrane::new(3)),
+        Box::new(BatchCrane::new(2)),
+    ]
+}
@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    command::{CraneMove, InvalidMove},
+    crane::{Crane, MoveStats},
+    stacks::CrateStacks,
+};
+
+struct Applied {
+    command: CraneMove,
+    taken: Vec<String>,
+    stats: MoveStats,
+}
+
+/// Operation log of a single crane working through a command stream. Every applied move keeps
+/// crates it took, so the log can be stepped back and forth to any command index.
+pub struct History<'a> {
+    crane: &'a dyn Crane,
+    commands: &'a [CraneMove],
+    stacks: CrateStacks,
+    applied: Vec<Applied>,
+}
+
+impl<'a> History<'a> {
+    pub fn new(crane: &'a dyn Crane, initial: &CrateStacks, commands: &'a [CraneMove]) -> Self {
+        Self {
+            crane,
+            commands,
+            stacks: initial.clone(),
+            applied: Vec::with_capacity(commands.len()),
+        }
+    }
+
+    pub fn crane(&self) -> &dyn Crane {
+        self.crane
+    }
+
+    /// Number of commands applied so far.
+    pub fn position(&self) -> usize {
+        self.applied.len()
+    }
+
+    pub fn stacks(&self) -> &CrateStacks {
+        &self.stacks
+    }
+
+    pub fn snapshot(&self) -> CrateStacks {
+        self.stacks.clone()
+    }
+
+    /// Statistics of all moves applied so far.
+    pub fn stats(&self) -> MoveStats {
+        let mut total = MoveStats::default();
+        for applied in &self.applied {
+            total += applied.stats;
+        }
+
+        total
+    }
+
+    /// Applies the next command. Returns `None` if all commands are already applied.
+    pub fn step_forward(&mut self) -> Result<Option<MoveStats>, InvalidMove> {
+        let command = match self.commands.get(self.position()) {
+            Some(command) => *command,
+            None => return Ok(None),
+        };
+
+        let taken = command
+            .taken(&self.stacks.stacks)
+            .map(<[String]>::to_vec)
+            .unwrap_or_default();
+        let stats = command.execute(&mut self.stacks.stacks, self.crane)?;
+
+        self.applied.push(Applied {
+            command,
+            taken,
+            stats,
+        });
+
+        Ok(Some(stats))
+    }
+
+    /// Reverts the last applied command. Returns `false` if there is nothing to revert.
+    pub fn step_back(&mut self) -> bool {
+        match self.applied.pop() {
+            Some(Applied { command, taken, .. }) => {
+                command.undo(&mut self.stacks.stacks, taken);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the log to a state right after `position` commands were applied.
+    pub fn seek(&mut self, position: usize) -> Result<()> {
+        if position > self.commands.len() {
+            return Err(anyhow!(
+                "cannot seek to command #{}, there are only {} commands",
+                position,
+                self.commands.len()
+            ));
+        }
+
+        while self.position() > position {
+            self.step_back();
+        }
+
+        while self.position() < position {
+            self.step_forward()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the first number of applied commands after which both cranes produce different
+/// messages. Returns `None` if they agree on the whole command stream.
+pub fn first_divergence(a: &mut History, b: &mut History) -> Result<Option<usize>> {
+    a.seek(0)?;
+    b.seek(0)?;
+
+    loop {
+        if a.stacks().message() != b.stacks().message() {
+            return Ok(Some(a.position()));
+        }
+
+        if a.step_forward()?.is_none() || b.step_forward()?.is_none() {
+            return Ok(None);
+        }
+    }
+}
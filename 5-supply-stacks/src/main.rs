@@ -1,28 +1,190 @@
 use std::{io::stdin, io::BufReader};
 
 mod command;
+mod crane;
+mod history;
+mod planner;
 mod stacks;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-use command::CraneVersion;
+use crane::Crane;
+use history::History;
+use planner::Plan;
 use stacks::CrateStacks;
 
+#[derive(Default)]
+struct Options {
+    dump_after: Option<usize>,
+    diverge: Option<(String, String)>,
+    plan: Option<String>,
+    crane: Option<String>,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+}
+
+impl Options {
+    fn from_args() -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dump-after" => {
+                    let count = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--dump-after expects a command number"))?;
+                    options.dump_after = Some(count.parse()?);
+                }
+                "--diverge" => {
+                    let mut crane_name = || {
+                        args.next()
+                            .ok_or_else(|| anyhow!("--diverge expects two crane names"))
+                    };
+                    options.diverge = Some((crane_name()?, crane_name()?));
+                }
+                "--plan" => {
+                    options.plan = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--plan expects a message"))?,
+                    );
+                }
+                "--crane" => {
+                    options.crane = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--crane expects a crane name"))?,
+                    );
+                }
+                "--max-depth" => {
+                    let depth = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--max-depth expects a number of moves"))?;
+                    options.max_depth = Some(depth.parse()?);
+                }
+                "--max-nodes" => {
+                    let nodes = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--max-nodes expects a number of states"))?;
+                    options.max_nodes = Some(nodes.parse()?);
+                }
+                _ => return Err(anyhow!("unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn find_crane<'c>(cranes: &'c [Box<dyn Crane>], name: &str) -> Result<&'c dyn Crane> {
+    cranes
+        .iter()
+        .find(|crane| crane.name() == name)
+        .map(Box::as_ref)
+        .ok_or_else(|| anyhow!("unknown crane: {}", name))
+}
+
 fn main() -> Result<()> {
-    let (reader, mut field) = CrateStacks::read(BufReader::new(stdin()))?;
+    let options = Options::from_args()?;
+    let (reader, field) = CrateStacks::read(BufReader::new(stdin()))?;
+    let commands = command::read_all(reader).collect::<Result<Vec<_>>>()?;
 
-    let mut field_cloned = field.clone();
+    let cranes = crane::registered();
+    let mut histories = cranes
+        .iter()
+        .map(|crane| History::new(crane.as_ref(), &field, &commands))
+        .collect::<Vec<_>>();
 
-    for command in command::read_all(reader) {
-        let command = command?;
+    // Running all commands first reports an invalid one before anything gets printed.
+    for history in &mut histories {
+        history.seek(commands.len())?;
+    }
 
-        command.execute(&mut field.stacks, CraneVersion::CraneMover9000);
-        command.execute(&mut field_cloned.stacks, CraneVersion::CraneMover9001);
+    if let Some(executed) = options.dump_after {
+        println!("Stacks after {} commands.", executed);
+        for history in &mut histories {
+            history.seek(executed)?;
+            println!("{}:", history.crane().name());
+            print!("{}", history.stacks());
+        }
+        println!();
+    }
+
+    if let Some((a, b)) = &options.diverge {
+        let mut a = History::new(find_crane(&cranes, a)?, &field, &commands);
+        let mut b = History::new(find_crane(&cranes, b)?, &field, &commands);
+
+        match history::first_divergence(&mut a, &mut b)? {
+            Some(executed) => {
+                println!(
+                    "{} and {} diverge after {} commands.",
+                    a.crane().name(),
+                    b.crane().name(),
+                    executed
+                );
+                for history in [&a, &b] {
+                    println!("{}: {}", history.crane().name(), history.stacks().message());
+                    print!("{}", history.snapshot());
+                }
+            }
+            None => println!(
+                "{} and {} never diverge.",
+                a.crane().name(),
+                b.crane().name()
+            ),
+        }
+        println!();
+    }
+
+    if let Some(message) = &options.plan {
+        let crane = find_crane(
+            &cranes,
+            options.crane.as_deref().unwrap_or("CraneMover9000"),
+        )?;
+        let max_depth = options.max_depth.unwrap_or(6);
+        let max_nodes = options.max_nodes.unwrap_or(2_000_000);
+
+        match planner::plan(&field, message, crane, max_depth, max_nodes) {
+            Plan::Found(moves) => {
+                println!(
+                    "{} forms {} after {} moves:",
+                    crane.name(),
+                    message,
+                    moves.len()
+                );
+                for command in moves {
+                    println!("{}", command);
+                }
+            }
+            Plan::Unreachable => println!("{} can never form {}.", crane.name(), message),
+            Plan::TooDeep => println!(
+                "{} cannot form {} in at most {} moves.",
+                crane.name(),
+                message,
+                max_depth
+            ),
+            Plan::GaveUp => println!(
+                "{} gave up forming {} after expanding {} states.",
+                crane.name(),
+                message,
+                max_nodes
+            ),
+        }
+        println!();
     }
 
     println!("Crates form a message after crane finishes its work.");
-    println!("CraneMover9000: {}", field.message());
-    println!("CraneMover9001: {}", field_cloned.message());
+    for history in &mut histories {
+        history.seek(commands.len())?;
+
+        let stats = history.stats();
+        println!(
+            "{}: {} ({} lifts, {} crates moved)",
+            history.crane().name(),
+            history.stacks().message(),
+            stats.lifts,
+            stats.crates
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,337 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use crate::{command::CraneMove, crane::Crane, stacks::CrateStacks};
+
+pub enum Plan {
+    Found(Vec<CraneMove>),
+    /// Crates needed to form the message are not in the stacks at all.
+    Unreachable,
+    /// No plan within the depth limit.
+    TooDeep,
+    /// Search expanded more states than allowed, before finding a plan or ruling it out.
+    GaveUp,
+}
+
+/// Searches for the shortest sequence of moves that makes `crane` produce `message` from `start`,
+/// using IDA* bounded by `max_depth` moves and `max_nodes` expanded states.
+pub fn plan(
+    start: &CrateStacks,
+    message: &str,
+    crane: &dyn Crane,
+    max_depth: usize,
+    max_nodes: usize,
+) -> Plan {
+    let mut available = HashMap::new();
+    for label in start.stacks.iter().flatten() {
+        *available.entry(label.as_str()).or_insert(0) += 1;
+    }
+
+    // Labels are numbered, so states are cheap to copy and compare.
+    let ids = available
+        .keys()
+        .enumerate()
+        .map(|(id, label)| (*label, id as u16))
+        .collect::<HashMap<_, _>>();
+
+    let spellings = spellings(message, &available)
+        .into_iter()
+        .filter(|labels| {
+            labels.len() <= start.stacks.len()
+                && counted(labels.iter().copied())
+                    .iter()
+                    .all(|(label, count)| available[label] >= *count)
+        })
+        .map(|labels| labels.into_iter().map(|label| ids[label]).collect())
+        .collect::<Vec<_>>();
+
+    if spellings.is_empty() || (message.is_empty() && !available.is_empty()) {
+        return Plan::Unreachable;
+    }
+
+    let crates = available.values().sum::<usize>();
+    let mut search = Search {
+        spellings,
+        lifts: (0..=crates).map(|count| crane.lifts(count)).collect(),
+        stacks: start
+            .stacks
+            .iter()
+            .map(|stack| stack.iter().map(|label| ids[label.as_str()]).collect())
+            .collect(),
+        path: vec![],
+        seen: HashMap::new(),
+        nodes: 0,
+        max_nodes,
+    };
+
+    let mut bound = search.heuristic();
+    while bound <= max_depth {
+        search.seen.clear();
+        search.seen.insert(search.state_key(), 0);
+
+        match search.deepen(bound) {
+            Deepen::Found => {
+                return Plan::Found(
+                    search
+                        .path
+                        .iter()
+                        .enumerate()
+                        .map(|(index, (count, source, target))| {
+                            CraneMove::new(index, *count, source + 1, target + 1)
+                        })
+                        .collect(),
+                )
+            }
+            Deepen::Exceeded(next_bound) => bound = next_bound,
+            Deepen::Exhausted => return Plan::Unreachable,
+            Deepen::GaveUp => return Plan::GaveUp,
+        }
+    }
+
+    Plan::TooDeep
+}
+
+enum Deepen {
+    Found,
+    /// Smallest estimate that went over the bound.
+    Exceeded(usize),
+    /// Every state was visited without exceeding the bound.
+    Exhausted,
+    GaveUp,
+}
+
+/// Every way to split `message` into labels of crates that are in the stacks.
+fn spellings<'l>(message: &str, labels: &HashMap<&'l str, usize>) -> Vec<Vec<&'l str>> {
+    if message.is_empty() {
+        return vec![vec![]];
+    }
+
+    labels
+        .keys()
+        .filter(|label| message.starts_with(*label))
+        .flat_map(|label| {
+            spellings(&message[label.len()..], labels)
+                .into_iter()
+                .map(move |mut rest| {
+                    rest.insert(0, label);
+                    rest
+                })
+        })
+        .collect()
+}
+
+fn counted<'l>(labels: impl Iterator<Item = &'l str>) -> HashMap<&'l str, usize> {
+    let mut counts = HashMap::new();
+    for label in labels {
+        *counts.entry(label).or_insert(0) += 1;
+    }
+
+    counts
+}
+
+/// Work a single stack needs before it ends with the right top.
+#[derive(Clone, Copy, Default)]
+struct Cost {
+    /// Top of the stack has to change.
+    wrong: usize,
+    /// Stack has to be a target of some move, as the needed crate is not inside of it.
+    target: usize,
+    /// Stack has to be a source of some move, as it has to end up empty.
+    source: usize,
+}
+
+/// Cost of making `stack` end with `needed` on top, or end empty if `needed` is `None`.
+fn stack_cost(stack: &[u16], needed: Option<u16>) -> Cost {
+    match (stack.split_last(), needed) {
+        (None, None) => Cost::default(),
+        (Some(_), None) => Cost {
+            wrong: 1,
+            target: 0,
+            source: 1,
+        },
+        (Some((top, _)), Some(needed)) if *top == needed => Cost::default(),
+        (Some((_, below)), Some(needed)) => Cost {
+            wrong: 1,
+            target: (!below.contains(&needed)) as usize,
+            source: 0,
+        },
+        (None, Some(_)) => Cost {
+            wrong: 1,
+            target: 1,
+            source: 0,
+        },
+    }
+}
+
+struct Search {
+    /// Labels needed on top of non-empty stacks, in order, for every way the message can be
+    /// spelled.
+    spellings: Vec<Vec<u16>>,
+    /// Lifts of the crane, indexed by number of crates moved.
+    lifts: Vec<Vec<usize>>,
+    stacks: Vec<Vec<u16>>,
+    /// Moves as `(count, source, target)`, stacks indexed from 0.
+    path: Vec<(usize, usize, usize)>,
+    /// Fewest moves after which a state was reached in the current iteration, by hash of the
+    /// state. A collision could only hide a state, never produce a wrong plan.
+    seen: HashMap<u64, usize>,
+    nodes: usize,
+    max_nodes: usize,
+}
+
+impl Search {
+    fn is_goal(&self) -> bool {
+        let tops = self
+            .stacks
+            .iter()
+            .flat_map(|stack| stack.last())
+            .copied()
+            .collect::<Vec<_>>();
+
+        self.spellings.contains(&tops)
+    }
+
+    fn state_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.stacks.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Lower bound on moves left. A move changes tops of two stacks only, it is a source for
+    /// one of them and a target for the other. So it fixes at most two wrong tops, at most one
+    /// stack missing the needed crate altogether and at most one stack that must end empty.
+    fn heuristic(&self) -> usize {
+        self.spellings
+            .iter()
+            .map(|needed| {
+                let cost = self.alignment_cost(needed);
+                cost.wrong.div_ceil(2).max(cost.target).max(cost.source)
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Sums of costs of stacks, when stacks ending empty are chosen to minimize each of them.
+    fn alignment_cost(&self, needed: &[u16]) -> Cost {
+        let (stacks, labels) = (self.stacks.len(), needed.len());
+
+        if stacks == labels {
+            return self
+                .stacks
+                .iter()
+                .zip(needed)
+                .map(|(stack, label)| stack_cost(stack, Some(*label)))
+                .fold(Cost::default(), |total, cost| Cost {
+                    wrong: total.wrong + cost.wrong,
+                    target: total.target + cost.target,
+                    source: total.source + cost.source,
+                });
+        }
+
+        // Stacks keep their order in the message, so some of them end empty in between.
+        let unreachable = Cost {
+            wrong: usize::MAX / 2,
+            target: usize::MAX / 2,
+            source: usize::MAX / 2,
+        };
+        let mut best = vec![vec![unreachable; labels + 1]; stacks + 1];
+        best[0][0] = Cost::default();
+
+        for (idx, stack) in self.stacks.iter().enumerate() {
+            for used in 0..=labels.min(idx) {
+                let current = best[idx][used];
+                let options = [(used, None), (used + 1, needed.get(used).copied())];
+
+                for (next, label) in options {
+                    if next > labels || (next == used + 1 && label.is_none()) {
+                        continue;
+                    }
+
+                    let cost = stack_cost(stack, label);
+                    let entry = &mut best[idx + 1][next];
+                    entry.wrong = entry.wrong.min(current.wrong + cost.wrong);
+                    entry.target = entry.target.min(current.target + cost.target);
+                    entry.source = entry.source.min(current.source + cost.source);
+                }
+            }
+        }
+
+        best[stacks][labels]
+    }
+
+    /// Applies a move and returns crates it took, bottom first.
+    fn apply(&mut self, count: usize, source: usize, target: usize) -> Vec<u16> {
+        let from = &mut self.stacks[source];
+        let taken = from.split_off(from.len() - count);
+
+        let mut rest = &taken[..];
+        for lift in &self.lifts[count] {
+            let (remaining, lifted) = rest.split_at(rest.len() - lift);
+            self.stacks[target].extend_from_slice(lifted);
+            rest = remaining;
+        }
+
+        taken
+    }
+
+    fn revert(&mut self, source: usize, target: usize, taken: Vec<u16>) {
+        let to = &mut self.stacks[target];
+        to.truncate(to.len() - taken.len());
+        self.stacks[source].extend(taken);
+    }
+
+    fn deepen(&mut self, bound: usize) -> Deepen {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return Deepen::GaveUp;
+        }
+
+        let estimate = self.path.len() + self.heuristic();
+        if estimate > bound {
+            return Deepen::Exceeded(estimate);
+        }
+
+        if self.is_goal() {
+            return Deepen::Found;
+        }
+
+        let depth = self.path.len() + 1;
+        let mut result = Deepen::Exhausted;
+
+        for source in 0..self.stacks.len() {
+            for target in (0..self.stacks.len()).filter(|target| *target != source) {
+                for count in 1..=self.stacks[source].len() {
+                    let taken = self.apply(count, source, target);
+                    let key = self.state_key();
+
+                    if self.seen.get(&key).is_none_or(|seen| depth < *seen) {
+                        self.seen.insert(key, depth);
+                        self.path.push((count, source, target));
+
+                        match self.deepen(bound) {
+                            Deepen::Found => return Deepen::Found,
+                            Deepen::GaveUp => return Deepen::GaveUp,
+                            Deepen::Exceeded(next) => {
+                                result = match result {
+                                    Deepen::Exceeded(current) => {
+                                        Deepen::Exceeded(current.min(next))
+                                    }
+                                    _ => Deepen::Exceeded(next),
+                                };
+                            }
+                            Deepen::Exhausted => {}
+                        }
+
+                        self.path.pop();
+                    }
+
+                    self.revert(source, target, taken);
+                }
+            }
+        }
+
+        result
+    }
+}
@@ -1,94 +1,181 @@
 use nom::{
-    branch::alt,
-    bytes::streaming::{tag, take},
-    character::streaming::{anychar, digit1, line_ending, space1},
-    combinator::{eof, map, map_parser, opt},
-    multi::{many1, many_till, separated_list0},
-    sequence::{delimited, terminated},
-    IResult,
+    bytes::complete::{is_not, tag},
+    character::complete::{digit1, space0},
+    combinator::{all_consuming, map_res},
+    multi::many1,
+    sequence::{delimited, preceded, terminated},
+    IResult, Offset,
 };
 
 use anyhow::{anyhow, Result};
 
-use std::io::BufRead;
+use std::{fmt, io::BufRead};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CrateStacks {
-    pub stacks: Vec<Vec<char>>,
+    pub stacks: Vec<Vec<String>>,
+}
+
+/// Position of a stack number in the numbering footer, in characters.
+struct StackNumber {
+    start: usize,
+    end: usize,
 }
 
 impl CrateStacks {
     pub fn message(&self) -> String {
-        self.stacks.iter().flat_map(|stack| stack.last()).collect()
+        self.stacks
+            .iter()
+            .flat_map(|stack| stack.last())
+            .map(String::as_str)
+            .collect()
     }
 
-    fn parse(input: &str) -> IResult<&str, Self> {
-        map(many_till(stack_level, stack_numbering), |(stacks, _)| {
-            let stack_size = stacks.len();
+    /// Builds stacks from drawing lines (top first) and numbering footer. Width of a cell is
+    /// inferred from distance between stack numbers, so crates can have labels of any length.
+    fn parse(drawing: &[String], footer: &str, numbers: &[StackNumber]) -> Result<Self> {
+        let width = match numbers {
+            [] => return Err(anyhow!("numbering footer is empty")),
+            [_] => drawing
+                .iter()
+                .map(|line| line.trim_end().chars().count())
+                .chain([footer.trim_end().len()])
+                .max()
+                .unwrap_or(0),
+            [first, .., last] => {
+                // Centers are doubled to stay in integers; numbers with a different amount of
+                // digits can be off by half a character, so the pitch is rounded.
+                let gaps = 2 * (numbers.len() - 1);
+                let spread = (last.start + last.end) - (first.start + first.end);
+                let pitch = (spread + gaps / 2) / gaps;
+
+                pitch
+                    .checked_sub(1)
+                    .filter(|width| *width > 0)
+                    .ok_or_else(|| anyhow!("stack numbers are too close to each other"))?
+            }
+        };
+        let pitch = width + 1;
+
+        let mut stacks = vec![Vec::with_capacity(drawing.len()); numbers.len()];
+
+        for (level, line) in drawing.iter().enumerate().rev() {
+            let line = line.chars().collect::<Vec<_>>();
 
-            let mut parsed = vec![Vec::with_capacity(stack_size); stacks[stack_size - 1].len()];
+            for (idx, cell) in line.chunks(pitch).enumerate() {
+                let (cell, separator) = cell.split_at(cell.len().min(width));
+                let cell = cell.iter().collect::<String>();
 
-            for slice in stacks.into_iter().rev() {
-                for (idx, content) in slice.into_iter().enumerate() {
-                    if let Some(c) = content {
-                        parsed[idx].push(c);
-                    }
+                if separator.iter().any(|c| *c != ' ') {
+                    return Err(anyhow!(
+                        "crate in line {} is not aligned with stack numbers",
+                        level + 1
+                    ));
+                }
+
+                if let Some(label) = stack_crate(&cell)? {
+                    stacks
+                        .get_mut(idx)
+                        .ok_or_else(|| anyhow!("crate {} is outside of numbered stacks", label))?
+                        .push(label);
                 }
             }
+        }
 
-            CrateStacks { stacks: parsed }
-        })(input)
+        Ok(CrateStacks { stacks })
     }
 
     pub fn read(mut reader: impl BufRead) -> Result<(impl BufRead, Self)> {
         let mut buf = String::new();
-
-        while let Ok(num_bytes) = reader.read_line(&mut buf) {
-            if num_bytes == 0 {
-                break;
+        let mut drawing = vec![];
+
+        while reader.read_line(&mut buf)? > 0 {
+            let line = buf.trim_end_matches(['\r', '\n']);
+
+            if let Ok((_, numbers)) = stack_numbering(line) {
+                let numbers = numbers
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, (number, position))| {
+                        (number == idx + 1)
+                            .then_some(position)
+                            .ok_or_else(|| anyhow!("stack {} is numbered {}", idx + 1, number))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                let stacks = Self::parse(&drawing, line, &numbers)?;
+                return Ok((reader, stacks));
             }
 
-            let parsed = Self::parse(&buf);
+            drawing.push(line.to_owned());
+            buf.truncate(0);
+        }
 
-            match parsed {
-                Ok((rest, stacks)) => {
-                    if !rest.is_empty() {
-                        return Err(anyhow!("data lingering"));
-                    }
+        Err(anyhow!("input is invalid, stack numbering not found"))
+    }
+}
 
-                    return Ok((reader, stacks));
-                }
-                Err(e) => {
-                    if e.is_incomplete() {
-                        continue;
-                    } else {
-                        return Err(e).map_err(|e| anyhow!("failed to parse input: {}", e));
-                    }
-                }
-            }
+impl fmt::Display for CrateStacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        let width = self
+            .stacks
+            .iter()
+            .flatten()
+            .map(|label| label.chars().count() + 2)
+            .chain([3, self.stacks.len().to_string().len()])
+            .max()
+            .unwrap_or(3);
+
+        for level in (0..height).rev() {
+            let cells = self
+                .stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(label) => format!("{:^width$}", format!("[{}]", label)),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<_>>();
+
+            writeln!(f, "{}", cells.join(" "))?;
         }
 
-        Err(anyhow!("input is invalid"))
+        let numbering = (1..=self.stacks.len())
+            .map(|number| format!("{:^width$}", number))
+            .collect::<Vec<_>>();
+
+        writeln!(f, "{}", numbering.join(" "))
     }
 }
 
-fn stack_crate(line: &str) -> IResult<&str, Option<char>> {
-    map_parser(take(3usize), opt(delimited(tag("["), anychar, tag("]"))))(line)
-}
+fn stack_crate(cell: &str) -> Result<Option<String>> {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return Ok(None);
+    }
+
+    let parsed: IResult<&str, &str> =
+        all_consuming(delimited(tag("["), is_not("[] "), tag("]")))(cell);
 
-fn stack_level(line: &str) -> IResult<&str, Vec<Option<char>>> {
-    terminated(
-        separated_list0(tag(" "), stack_crate),
-        alt((line_ending, eof)),
-    )(line)
+    parsed
+        .map(|(_, label)| Some(label.to_owned()))
+        .map_err(|_| anyhow!("failed to parse crate: {}", cell))
 }
 
-fn stack_numbering(line: &str) -> IResult<&str, ()> {
-    map(
-        terminated(
-            many1(delimited(opt(space1), digit1, space1)),
-            alt((line_ending, eof)),
-        ),
-        |_| (),
-    )(line)
+fn stack_numbering(line: &str) -> IResult<&str, Vec<(usize, StackNumber)>> {
+    all_consuming(terminated(
+        many1(map_res(preceded(space0, digit1), |number: &str| {
+            let start = line.offset(number);
+            number.parse().map(|parsed| {
+                (
+                    parsed,
+                    StackNumber {
+                        start,
+                        end: start + number.len(),
+                    },
+                )
+            })
+        })),
+        space0,
+    ))(line)
 }
@@ -0,0 +1,134 @@
+/// Sliding window over a byte stream which knows whether all bytes in the window are unique.
+/// Keeps only the window and per-byte counts, so it works in constant memory.
+pub struct MarkerDetector {
+    window: Vec<u8>,
+    counts: [usize; 256],
+    repeated: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    pub fn new(len: usize) -> Self {
+        assert!(len > 0, "marker window must not be empty");
+
+        Self {
+            window: vec![0; len],
+            counts: [0; 256],
+            repeated: 0,
+            position: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Number of bytes processed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Pushes next byte of the stream. Returns `true` if it completes a marker.
+    pub fn push(&mut self, byte: u8) -> bool {
+        let slot = self.position % self.len();
+
+        if self.position >= self.len() {
+            let outgoing = self.window[slot] as usize;
+            self.counts[outgoing] -= 1;
+            if self.counts[outgoing] == 1 {
+                self.repeated -= 1;
+            }
+        }
+
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeated += 1;
+        }
+
+        self.position += 1;
+        self.position >= self.len() && self.repeated == 0
+    }
+}
+
+/// Markers of given length found in a stream. Positions of all of them are kept only on request,
+/// otherwise the report takes constant memory.
+pub struct MarkerReport {
+    pub len: usize,
+    first: Option<usize>,
+    last: Option<usize>,
+    count: usize,
+    positions: Option<Vec<usize>>,
+}
+
+impl MarkerReport {
+    fn new(len: usize, keep_positions: bool) -> Self {
+        Self {
+            len,
+            first: None,
+            last: None,
+            count: 0,
+            positions: keep_positions.then(Vec::new),
+        }
+    }
+
+    fn record(&mut self, position: usize) {
+        self.first.get_or_insert(position);
+        self.last = Some(position);
+        self.count += 1;
+
+        if let Some(positions) = &mut self.positions {
+            positions.push(position);
+        }
+    }
+
+    pub fn first(&self) -> Option<usize> {
+        self.first
+    }
+
+    pub fn last(&self) -> Option<usize> {
+        self.last
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Every position at which a marker completes, if they were kept.
+    pub fn positions(&self) -> Option<&[usize]> {
+        self.positions.as_deref()
+    }
+}
+
+/// Detectors for several window lengths, fed with the stream piece by piece.
+/// Whitespace is not a part of the packet and gets skipped.
+pub struct Scanner {
+    detectors: Vec<MarkerDetector>,
+    reports: Vec<MarkerReport>,
+}
+
+impl Scanner {
+    pub fn new(lens: &[usize], keep_positions: bool) -> Self {
+        Self {
+            detectors: lens.iter().map(|len| MarkerDetector::new(*len)).collect(),
+            reports: lens
+                .iter()
+                .map(|len| MarkerReport::new(*len, keep_positions))
+                .collect(),
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        for byte in chunk.iter().filter(|byte| !byte.is_ascii_whitespace()) {
+            for (detector, report) in self.detectors.iter_mut().zip(&mut self.reports) {
+                if detector.push(*byte) {
+                    report.record(detector.position());
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> Vec<MarkerReport> {
+        self.reports
+    }
+}
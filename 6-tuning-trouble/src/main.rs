@@ -1,52 +1,171 @@
-use anyhow::Result;
-use std::io::{stdin, BufRead, BufReader};
+use anyhow::{anyhow, Result};
+use std::io::{stdin, BufReader};
 
-fn read(mut reader: impl BufRead) -> Result<String> {
-    let mut packet = String::new();
-    reader.read_to_string(&mut packet)?;
+mod detector;
+mod streams;
 
-    Ok(packet.trim().to_owned())
+use detector::MarkerReport;
+use streams::StreamReport;
+
+const PRELUDE_LEN: usize = 4;
+const MESSAGE_START_LEN: usize = 14;
+
+#[derive(Default)]
+struct Options {
+    windows: Vec<usize>,
+    json: bool,
+    positions: bool,
 }
 
-fn find_all_unique_piece(packet: &str, piece_len: usize) -> Option<usize> {
-    for (idx, maybe_unique) in packet.as_bytes().windows(piece_len).enumerate() {
-        let is_unique = !(0..piece_len)
-            .into_iter()
-            .any(|idx| maybe_unique[..idx].contains(&maybe_unique[idx]));
+impl Options {
+    fn from_args() -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
 
-        if is_unique {
-            return Some(idx + piece_len);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--window" => {
+                    let len = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--window expects a marker length"))?
+                        .parse()?;
+                    if len == 0 {
+                        return Err(anyhow!("marker length must be positive"));
+                    }
+                    options.windows.push(len);
+                }
+                "--json" => options.json = true,
+                "--positions" => options.positions = true,
+                _ => return Err(anyhow!("unknown argument: {}", arg)),
+            }
         }
-    }
 
-    None
-}
+        if options.windows.is_empty() {
+            options.windows = vec![PRELUDE_LEN, MESSAGE_START_LEN];
+        }
 
-fn packet_prelude_position(packet: &str) -> Option<usize> {
-    find_all_unique_piece(packet, 4)
+        Ok(options)
+    }
 }
 
-fn message_start_position(packet: &str) -> Option<usize> {
-    find_all_unique_piece(packet, 14)
+fn marker_name(len: usize) -> String {
+    match len {
+        PRELUDE_LEN => "prelude".into(),
+        MESSAGE_START_LEN => "message start".into(),
+        len => format!("marker of {} characters", len),
+    }
 }
 
-fn print_result(result: Option<usize>, result_type: &'static str) {
-    match result {
-        Some(position) => {
+fn print_result(report: &MarkerReport) {
+    let result_type = marker_name(report.len);
+
+    match (report.first(), report.last()) {
+        (Some(first), Some(last)) => {
+            println!(
+                "  {} characters needs to be processed before {} is detected.",
+                first, result_type
+            );
             println!(
-                "{} characters needs to be processed before {} is detected.",
-                position, result_type
+                "  Found {} {} markers, last one after {} characters.",
+                report.count(),
+                result_type,
+                last
             );
+
+            if let Some(positions) = report.positions() {
+                let positions = positions
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                println!(
+                    "  Markers complete after {} characters.",
+                    positions.join(", ")
+                );
+            }
         }
-        None => println!("Couldn't find {} in the packet.", result_type),
+        _ => println!("  Couldn't find {} in the packet.", result_type),
     }
 }
 
+fn json_or_null(value: Option<usize>) -> String {
+    value.map_or_else(|| "null".into(), |value| value.to_string())
+}
+
+fn print_stream(stream: &StreamReport) {
+    println!("Line {}:", stream.line);
+    stream.markers.iter().for_each(print_result);
+}
+
+fn print_failures(streams: &[StreamReport]) {
+    for stream in streams.iter().filter(|stream| stream.failed()) {
+        let missing = stream
+            .markers
+            .iter()
+            .filter(|report| report.count() == 0)
+            .map(|report| marker_name(report.len))
+            .collect::<Vec<_>>();
+
+        println!(
+            "Line {} failed, missing {}.",
+            stream.line,
+            missing.join(", ")
+        );
+    }
+}
+
+fn windows_json(reports: &[MarkerReport]) -> String {
+    let windows = reports
+        .iter()
+        .map(|report| {
+            let positions = report
+                .positions()
+                .unwrap_or_default()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            format!(
+                r#"{{"length":{},"first":{},"last":{},"count":{},"positions":[{}]}}"#,
+                report.len,
+                json_or_null(report.first()),
+                json_or_null(report.last()),
+                report.count(),
+                positions.join(",")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", windows.join(","))
+}
+
+fn print_json(streams: &[StreamReport]) {
+    let streams = streams
+        .iter()
+        .map(|stream| {
+            format!(
+                r#"{{"line":{},"failed":{},"windows":{}}}"#,
+                stream.line,
+                stream.failed(),
+                windows_json(&stream.markers)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    println!(r#"{{"streams":[{}]}}"#, streams.join(","));
+}
+
 fn main() -> Result<()> {
-    let packet = read(BufReader::new(stdin()))?;
+    let options = Options::from_args()?;
+    // Positions of every marker are kept only when they get printed.
+    let keep_positions = options.json || options.positions;
+    let streams = streams::scan_lines(BufReader::new(stdin()), &options.windows, keep_positions)?;
 
-    print_result(packet_prelude_position(&packet), "prelude");
-    print_result(message_start_position(&packet), "message start");
+    if options.json {
+        print_json(&streams);
+    } else {
+        streams.iter().for_each(print_stream);
+        print_failures(&streams);
+    }
 
     Ok(())
 }
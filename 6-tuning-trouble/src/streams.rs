@@ -0,0 +1,114 @@
+use std::{
+    io::{self, BufRead},
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use crate::detector::{MarkerReport, Scanner};
+
+/// Largest piece of a line handed to a worker at once.
+const CHUNK_LEN: usize = 64 * 1024;
+/// Pieces of a single line waiting for their worker.
+const CHUNKS_QUEUED: usize = 4;
+
+/// Markers found in a single line of the capture.
+pub struct StreamReport {
+    pub line: usize,
+    pub markers: Vec<MarkerReport>,
+}
+
+impl StreamReport {
+    /// Whether any of the markers was not found in this stream.
+    pub fn failed(&self) -> bool {
+        self.markers.iter().any(|report| report.count() == 0)
+    }
+}
+
+/// Treats every line as an independent datastream and scans them in parallel.
+/// Blank lines are skipped, but they still count towards line numbers.
+///
+/// Lines are never read whole. Every line gets a worker, which receives the line in chunks of
+/// at most `CHUNK_LEN` bytes while it is being read, so memory stays bounded by
+/// `CHUNK_LEN * CHUNKS_QUEUED` per active or queued line, no matter how long lines are.
+pub fn scan_lines(
+    mut reader: impl BufRead,
+    lens: &[usize],
+    keep_positions: bool,
+) -> io::Result<Vec<StreamReport>> {
+    let workers = thread::available_parallelism().map_or(1, |workers| workers.get());
+    let (line_tx, line_rx) = mpsc::sync_channel::<(usize, mpsc::Receiver<Vec<u8>>)>(workers);
+    let (report_tx, report_rx) = mpsc::channel();
+    let line_rx = Mutex::new(line_rx);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let report_tx = report_tx.clone();
+            let line_rx = &line_rx;
+
+            scope.spawn(move || loop {
+                // PANIC: Lock is poisoned only if other worker panicked already.
+                let next = line_rx.lock().unwrap().recv();
+                let (line, chunks) = match next {
+                    Ok(next) => next,
+                    Err(_) => break,
+                };
+
+                let mut scanner = Scanner::new(lens, keep_positions);
+                for chunk in chunks {
+                    scanner.feed(&chunk);
+                }
+
+                let markers = scanner.finish();
+                if report_tx.send(StreamReport { line, markers }).is_err() {
+                    break;
+                }
+            });
+        }
+
+        let mut line = 1;
+        // Line is handed to a worker only once it turns out not to be blank.
+        let mut current = None::<mpsc::SyncSender<Vec<u8>>>;
+
+        loop {
+            let buffer = reader.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+
+            let (chunk, ends_line) = match buffer.iter().position(|byte| *byte == b'\n') {
+                Some(end) => (&buffer[..end.min(CHUNK_LEN)], end < CHUNK_LEN),
+                None => (&buffer[..buffer.len().min(CHUNK_LEN)], false),
+            };
+
+            if current.is_none() && !chunk.iter().all(u8::is_ascii_whitespace) {
+                let (chunk_tx, chunk_rx) = mpsc::sync_channel(CHUNKS_QUEUED);
+                // PANIC: Workers keep receiving until the channel is closed.
+                line_tx.send((line, chunk_rx)).unwrap();
+                current = Some(chunk_tx);
+            }
+
+            if let Some(chunk_tx) = &current {
+                // PANIC: Worker receives until the line is over.
+                chunk_tx.send(chunk.to_vec()).unwrap();
+            }
+
+            let consumed = chunk.len() + ends_line as usize;
+            reader.consume(consumed);
+
+            if ends_line {
+                current = None;
+                line += 1;
+            }
+        }
+
+        drop(current);
+        drop(line_tx);
+        Ok::<_, io::Error>(())
+    })?;
+
+    drop(report_tx);
+    let mut reports = report_rx.into_iter().collect::<Vec<_>>();
+    reports.sort_by_key(|report| report.line);
+
+    Ok(reports)
+}
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::filesystem::{node_sizes, FileSystem, FileSystemNode};
+
+pub enum Change {
+    Added(usize),
+    Removed(usize),
+    Resized(usize, usize),
+}
+
+pub struct Difference {
+    pub path: String,
+    pub is_dir: bool,
+    pub change: Change,
+}
+
+/// Paths of all nodes, including root, with whether they are a directory and their total size.
+fn snapshot(fs: &FileSystem) -> Result<BTreeMap<(String, bool), usize>> {
+    let (sizes, _) = node_sizes(fs)?;
+
+    Ok(std::iter::once(("/".to_owned(), 0))
+        .chain(fs.paths())
+        .map(|(path, inode)| {
+            let is_dir = fs.get(inode).is_some_and(FileSystemNode::is_dir);
+            ((path, is_dir), sizes[inode])
+        })
+        .collect())
+}
+
+/// Compares two filesystems by paths. A node which changed from a file to a directory or
+/// the other way around is reported as removed and added again.
+pub fn diff(old: &FileSystem, new: &FileSystem) -> Result<Vec<Difference>> {
+    let old = snapshot(old)?;
+    let mut new = snapshot(new)?;
+    let mut result = vec![];
+
+    for ((path, is_dir), old_size) in old {
+        let change = match new.remove(&(path.clone(), is_dir)) {
+            Some(new_size) if new_size == old_size => continue,
+            Some(new_size) => Change::Resized(old_size, new_size),
+            None => Change::Removed(old_size),
+        };
+
+        result.push(Difference {
+            path,
+            is_dir,
+            change,
+        });
+    }
+
+    result.extend(new.into_iter().map(|((path, is_dir), size)| Difference {
+        path,
+        is_dir,
+        change: Change::Added(size),
+    }));
+    result.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(result)
+}
+
+fn kind(is_dir: bool) -> &'static str {
+    if is_dir {
+        "dir"
+    } else {
+        "file"
+    }
+}
+
+pub fn render_text(differences: &[Difference]) -> Result<String> {
+    let mut result = String::new();
+
+    for Difference {
+        path,
+        is_dir,
+        change,
+    } in differences
+    {
+        match change {
+            Change::Added(size) => writeln!(result, "+ {} ({}, {})", path, kind(*is_dir), size)?,
+            Change::Removed(size) => writeln!(result, "- {} ({}, {})", path, kind(*is_dir), size)?,
+            Change::Resized(old, new) => writeln!(
+                result,
+                "~ {} ({}, {} -> {}, {:+})",
+                path,
+                kind(*is_dir),
+                old,
+                new,
+                *new as i128 - *old as i128
+            )?,
+        }
+    }
+
+    Ok(result)
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+pub fn render_json(differences: &[Difference]) -> String {
+    let (mut added, mut removed, mut resized) = (vec![], vec![], vec![]);
+
+    for Difference {
+        path,
+        is_dir,
+        change,
+    } in differences
+    {
+        let path = json_string(path);
+        match change {
+            Change::Added(size) => added.push(format!(
+                r#"{{"path":{},"dir":{},"size":{}}}"#,
+                path, is_dir, size
+            )),
+            Change::Removed(size) => removed.push(format!(
+                r#"{{"path":{},"dir":{},"size":{}}}"#,
+                path, is_dir, size
+            )),
+            Change::Resized(old, new) => resized.push(format!(
+                r#"{{"path":{},"dir":{},"old":{},"new":{},"delta":{}}}"#,
+                path,
+                is_dir,
+                old,
+                new,
+                *new as i128 - *old as i128
+            )),
+        }
+    }
+
+    format!(
+        r#"{{"added":[{}],"removed":[{}],"resized":[{}]}}"#,
+        added.join(","),
+        removed.join(","),
+        resized.join(",")
+    )
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Result};
+
+/// Nodes addressed by inode. Removed nodes leave an empty slot, so inodes are never reused.
+pub struct FileSystem(pub Vec<Option<FileSystemNode>>);
+
+#[derive(Debug)]
+pub enum FileSystemNode {
+    File(String, usize),
+    Dir(String, Vec<usize>, usize),
+}
+
+impl FileSystemNode {
+    pub fn is_dir(&self) -> bool {
+        matches!(self, FileSystemNode::Dir(_, _, _))
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            FileSystemNode::Dir(dname, ..) => dname,
+            FileSystemNode::File(fname, ..) => fname,
+        }
+    }
+
+    pub fn contents(&self) -> Option<&Vec<usize>> {
+        if let FileSystemNode::Dir(_, contents, _) = self {
+            Some(contents)
+        } else {
+            None
+        }
+    }
+
+    pub fn add_inode(&mut self, inode: usize) {
+        if let FileSystemNode::Dir(_, contents, ..) = self {
+            contents.push(inode);
+        }
+    }
+
+    pub fn remove_inode(&mut self, inode: usize) {
+        if let FileSystemNode::Dir(_, contents, ..) = self {
+            contents.retain(|child| *child != inode);
+        }
+    }
+
+    pub fn rename(&mut self, name: &str) {
+        match self {
+            FileSystemNode::Dir(dname, ..) => *dname = name.to_owned(),
+            FileSystemNode::File(fname, ..) => *fname = name.to_owned(),
+        }
+    }
+}
+
+impl FileSystem {
+    pub fn get(&self, inode: usize) -> Option<&FileSystemNode> {
+        self.0.get(inode).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, inode: usize) -> Option<&mut FileSystemNode> {
+        self.0.get_mut(inode).and_then(Option::as_mut)
+    }
+
+    pub fn children(&self, inode: usize) -> Option<impl Iterator<Item = (usize, &FileSystemNode)>> {
+        self.get(inode)
+            .and_then(FileSystemNode::contents)
+            .map(|contents| {
+                contents
+                    .iter()
+                    .filter_map(|child_inode| Some((*child_inode, self.get(*child_inode)?)))
+            })
+    }
+
+    /// Adds a node to a directory and returns its inode.
+    pub fn insert(&mut self, parent: usize, node: FileSystemNode) -> Result<usize> {
+        let inode = self.0.len();
+        self.get_mut(parent)
+            .filter(|parent| parent.is_dir())
+            .ok_or_else(|| anyhow!("inode {} is not a directory", parent))?
+            .add_inode(inode);
+        self.0.push(Some(node));
+
+        Ok(inode)
+    }
+
+    /// Moves a node to another directory under a new name.
+    pub fn relocate(&mut self, inode: usize, from: usize, to: usize, name: &str) -> Result<()> {
+        if let Some(from) = self.get_mut(from) {
+            from.remove_inode(inode);
+        }
+
+        self.get_mut(to)
+            .filter(|to| to.is_dir())
+            .ok_or_else(|| anyhow!("inode {} is not a directory", to))?
+            .add_inode(inode);
+
+        let node = self
+            .get_mut(inode)
+            .ok_or_else(|| anyhow!("inode {} does not exist", inode))?;
+        node.rename(name);
+        if let FileSystemNode::Dir(_, _, parent) = node {
+            *parent = to;
+        }
+
+        Ok(())
+    }
+
+    /// Removes a node from its parent directory, together with everything inside it.
+    pub fn remove(&mut self, parent: usize, inode: usize) {
+        if let Some(parent) = self.get_mut(parent) {
+            parent.remove_inode(inode);
+        }
+
+        let mut stack = vec![inode];
+        while let Some(current) = stack.pop() {
+            if let Some(FileSystemNode::Dir(_, contents, _)) = self.0[current].take() {
+                stack.extend(contents);
+            }
+        }
+    }
+
+    /// Whether directory `dir` is `ancestor` itself or lies somewhere inside of it.
+    pub fn is_within(&self, dir: usize, ancestor: usize) -> bool {
+        let mut current = dir;
+
+        loop {
+            if current == ancestor {
+                return true;
+            }
+
+            match self.get(current) {
+                Some(FileSystemNode::Dir(_, _, parent)) if current != 0 => current = *parent,
+                _ => return false,
+            }
+        }
+    }
+}
+
+pub fn dir_sizes(fs: &FileSystem) -> Result<Vec<usize>> {
+    let (sizes, dirs) = node_sizes(fs)?;
+
+    Ok(dirs.into_iter().map(|inode| sizes[inode]).collect())
+}
+
+/// Recursive sizes of nodes reachable from root, indexed by inode, together with inodes of
+/// reachable directories in order their traversal finished. Unreachable nodes have size 0.
+pub fn node_sizes(fs: &FileSystem) -> Result<(Vec<usize>, Vec<usize>)> {
+    let mut stack = vec![(0, 0, 0)];
+    let mut sizes = vec![0; fs.0.len()];
+    let mut dirs = vec![];
+    let mut last_parent = usize::MAX;
+
+    while !stack.is_empty() {
+        let idx = stack.len() - 1;
+        // PANIC: We've just checked that stack is empty.
+        let (current, _, parent_idx) = stack.last_mut().copied().unwrap();
+        let coming_back = last_parent == current;
+
+        if !coming_back {
+            use FileSystemNode::*;
+
+            for (inode, child) in fs
+                .children(current)
+                .ok_or_else(|| anyhow!("attempt to traverse non-dir inode: {}", current))?
+            {
+                match child {
+                    File(_, size) => {
+                        stack[idx].1 += size;
+                        sizes[inode] = *size;
+                    }
+                    Dir(..) => {
+                        stack.push((inode, 0, idx));
+                    }
+                }
+            }
+        }
+
+        if stack.last().unwrap().0 == current {
+            sizes[current] = stack[idx].1;
+            dirs.push(current);
+            stack[parent_idx].1 += stack[idx].1;
+            stack.pop();
+            if !stack.is_empty() {
+                last_parent = stack[parent_idx].0;
+            }
+        }
+    }
+
+    Ok((sizes, dirs))
+}
@@ -1,300 +1,224 @@
 use anyhow::{anyhow, Result};
-use std::io::{stdin, BufRead, BufReader};
+use std::{
+    fs::File,
+    io::{stdin, stdout, BufReader},
+    path::Path,
+};
+
+mod diff;
+mod filesystem;
+mod materialize;
+mod planner;
+mod query;
+mod render;
+mod repl;
+mod transcript;
+mod validation;
+
+use filesystem::{dir_sizes, FileSystem, FileSystemNode};
+use render::{SortBy, TreeOptions};
+use validation::Validation;
+
+const DISK_SIZE: usize = 70000000;
+const REQUIRED_FREE: usize = 30000000;
+
+fn answers(fs: &FileSystem) -> Result<()> {
+    let sizes = dir_sizes(fs)?;
 
-struct FileSystem(Vec<FileSystemNode>);
+    let at_most_100000_sum = sizes
+        .iter()
+        .copied()
+        .filter(|size| *size <= 100000)
+        .sum::<usize>();
 
-#[derive(Debug)]
-enum FileSystemNode {
-    File(String, usize),
-    Dir(String, Vec<usize>, usize),
-}
+    let total_used = sizes
+        .iter()
+        .copied()
+        .max()
+        .ok_or_else(|| anyhow!("expected at least one directory in input"))?;
 
-impl FileSystemNode {
-    fn is_dir(&self) -> bool {
-        matches!(self, FileSystemNode::Dir(_, _, _))
-    }
+    let minimum_directory = sizes
+        .iter()
+        .copied()
+        .filter(|size| total_used.saturating_sub(*size) <= DISK_SIZE - REQUIRED_FREE)
+        .min()
+        .ok_or_else(|| anyhow!("failed to find a directory matching conditions"))?;
 
-    fn name(&self) -> &str {
-        match self {
-            FileSystemNode::Dir(dname, ..) => dname,
-            FileSystemNode::File(fname, ..) => fname,
-        }
-    }
+    println!(
+        "Total sum of directories with more than 100000 bytes is {}",
+        at_most_100000_sum,
+    );
 
-    fn contents(&self) -> Option<&Vec<usize>> {
-        if let FileSystemNode::Dir(_, contents, _) = self {
-            Some(contents)
-        } else {
-            None
-        }
-    }
+    println!(
+        "Minimum directory that needs to be deleted to free up space has {} bytes",
+        minimum_directory
+    );
 
-    fn add_inode(&mut self, inode: usize) {
-        if let FileSystemNode::Dir(_, contents, ..) = self {
-            contents.push(inode);
-        }
-    }
+    Ok(())
 }
 
-impl FileSystem {
-    fn get(&self, inode: usize) -> Option<&FileSystemNode> {
-        self.0.get(inode)
-    }
+fn query(fs: &FileSystem, operation: &str, argument: &str) -> Result<()> {
+    use FileSystemNode::*;
 
-    fn get_mut(&mut self, inode: usize) -> Option<&mut FileSystemNode> {
-        self.0.get_mut(inode)
-    }
+    match operation {
+        "resolve" => {
+            let inode = fs
+                .resolve(argument)
+                .ok_or_else(|| anyhow!("no such file or directory: {}", argument))?;
 
-    fn children(&self, inode: usize) -> Option<impl Iterator<Item = (usize, &FileSystemNode)>> {
-        self.get(inode)
-            .and_then(FileSystemNode::contents)
-            .map(|contents| {
-                contents
-                    .iter()
-                    .map(|child_inode| (*child_inode, &self.0[*child_inode]))
-            })
+            match fs.get(inode) {
+                Some(File(_, size)) => println!("{}: file of {} bytes", argument, size),
+                Some(Dir(..)) => println!("{}: directory of {} bytes", argument, fs.size(inode)),
+                None => return Err(anyhow!("inode {} is missing", inode)),
+            }
+        }
+        "ls" => {
+            for (_, node) in fs.list(argument)? {
+                match node {
+                    File(name, size) => println!("{} {}", size, name),
+                    Dir(name, ..) => println!("dir {}", name),
+                }
+            }
+        }
+        "size" => {
+            let inode = fs
+                .resolve(argument)
+                .ok_or_else(|| anyhow!("no such file or directory: {}", argument))?;
+            println!("{}", fs.size(inode));
+        }
+        "find" => {
+            for (path, inode) in fs.find(argument) {
+                println!("{} {}", fs.size(inode), path);
+            }
+        }
+        _ => return Err(anyhow!("unknown query: {}", operation)),
     }
-}
 
-enum Command<'line> {
-    ChangeDir(&'line str),
-    List,
+    Ok(())
 }
 
-fn read_command(line: &str) -> Option<Command> {
-    use Command::*;
-
-    if line.starts_with("cd") {
-        Some(ChangeDir(&line[3..]))
-    } else if line == "ls" {
-        Some(List)
-    } else {
-        None
+fn tree(fs: &FileSystem, args: &[&str]) -> Result<()> {
+    let mut options = TreeOptions {
+        sort: SortBy::Listing,
+        max_depth: None,
+        human_readable: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--sort" => {
+                options.sort = match args.next() {
+                    Some(&"name") => SortBy::Name,
+                    Some(&"size") => SortBy::Size,
+                    _ => return Err(anyhow!("--sort expects name or size")),
+                };
+            }
+            "--depth" => {
+                let depth = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--depth expects a number"))?;
+                options.max_depth = Some(depth.parse()?);
+            }
+            "-h" | "--human-readable" => options.human_readable = true,
+            _ => return Err(anyhow!("unknown argument: {}", arg)),
+        }
     }
-}
 
-struct FileSystemBuilder {
-    fs: FileSystem,
-    cwd: usize,
+    print!("{}", render::render_tree(fs, &options)?);
+    Ok(())
 }
 
-impl FileSystemBuilder {
-    fn new() -> Self {
-        use FileSystemNode::*;
-
-        Self {
-            fs: FileSystem(vec![Dir("".into(), vec![], 0)]),
-            cwd: 0,
-        }
-    }
+fn plan(fs: &FileSystem, args: &[&str]) -> Result<()> {
+    let (mut disk_size, mut required_free) = (DISK_SIZE, REQUIRED_FREE);
 
-    fn cwd_dir(&self) -> Result<&FileSystemNode> {
-        self.fs
-            .get(self.cwd)
-            .ok_or_else(|| anyhow!("cwd is wrong, invalid index"))
-            .and_then(|node| {
-                node.is_dir()
-                    .then_some(node)
-                    .ok_or_else(|| anyhow!("cwd is wrong, not a directory"))
-            })
-    }
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| anyhow!("{} expects a number of bytes", arg))?
+            .parse()?;
 
-    fn cwd_dir_mut(&mut self) -> Result<&mut FileSystemNode> {
-        self.fs
-            .get_mut(self.cwd)
-            .ok_or_else(|| anyhow!("cwd is wrong, invalid index"))
-            .and_then(|node| {
-                node.is_dir()
-                    .then_some(node)
-                    .ok_or_else(|| anyhow!("cwd is wrong, not a directory"))
-            })
-    }
-
-    fn cwd_parent(&self) -> Result<usize> {
-        if let FileSystemNode::Dir(_, _, parent) = self.cwd_dir()? {
-            Ok(*parent)
-        } else {
-            panic!("cwd_dir logic is wrong and it returned a non-dir node");
+        match *arg {
+            "--disk" => disk_size = value,
+            "--free" => required_free = value,
+            _ => return Err(anyhow!("unknown argument: {}", arg)),
         }
     }
 
-    fn find_or_create_dir(&mut self, inner: &str) -> Result<usize> {
-        match self.find_node(inner)? {
-            Some(inode) => Ok(inode),
-            None => {
-                let new_inode = self.fs.0.len();
-                self.fs
-                    .0
-                    .push(FileSystemNode::Dir(inner.to_owned(), vec![], self.cwd));
-                self.cwd_dir_mut()?.add_inode(new_inode);
-                Ok(new_inode)
+    match planner::plan_deletion(fs, disk_size, required_free)? {
+        Some(plan) => {
+            println!("Deleting {} bytes frees up enough space:", plan.freed);
+            for (path, size) in plan.nodes {
+                println!("{} {}", size, path);
             }
         }
+        None => println!("Deleting everything does not free up enough space"),
     }
 
-    fn change_dir(&mut self, target: &str) -> Result<()> {
-        match target {
-            ".." => {
-                self.cwd = self.cwd_parent()?;
-            }
-            "/" => {
-                self.cwd = 0;
-            }
-            inner => {
-                self.cwd = self.find_or_create_dir(inner)?;
-            }
-        }
+    Ok(())
+}
 
-        Ok(())
-    }
+fn validate(args: &[&str]) -> Result<()> {
+    let validation = match args {
+        [] => Validation::Report,
+        ["--strict"] => Validation::Strict,
+        _ => return Err(anyhow!("usage: validate [--strict]")),
+    };
 
-    fn find_node(&self, name: &str) -> Result<Option<usize>> {
-        Ok(self
-            .fs
-            .children(self.cwd)
-            .into_iter()
-            .flatten()
-            .find(|(_, node)| node.name() == name)
-            .map(|(inode, _)| inode))
+    let (_, issues) = transcript::read_validated(BufReader::new(stdin()), validation)?;
+    if issues.is_empty() {
+        println!("Transcript is consistent");
     }
 
-    fn append_file(&mut self, name: String, size: usize) -> Result<usize> {
-        match self.find_node(&name)? {
-            Some(inode) => Ok(inode),
-            None => {
-                let new_inode = self.fs.0.len();
-                self.fs.0.push(FileSystemNode::File(name, size));
-                self.cwd_dir_mut()?.add_inode(new_inode);
-                Ok(new_inode)
-            }
-        }
+    for issue in issues {
+        println!("{}", issue);
     }
 
-    fn build(self) -> FileSystem {
-        self.fs
-    }
+    Ok(())
 }
 
-fn read(reader: impl BufRead) -> Result<FileSystem> {
-    let mut fs = FileSystemBuilder::new();
-
-    let mut is_listing = false;
-    for line in reader.lines() {
-        let line = line?;
-
-        if line.starts_with('$') {
-            is_listing = false;
-            let command = read_command(&line[2..]).ok_or_else(|| anyhow!("wrong input"))?;
-
-            use Command::*;
-            match command {
-                ChangeDir(target) => {
-                    fs.change_dir(target)?;
-                }
-                List => {
-                    is_listing = true;
-                }
-            }
-        } else if is_listing {
-            if line.starts_with("dir") {
-                fs.find_or_create_dir(&line[4..])?;
-            } else {
-                let mut parts = line.split_whitespace();
-                let size = parts
-                    .next()
-                    .ok_or_else(|| anyhow!("invalid input - wrong file format"))
-                    .and_then(|size| size.parse::<usize>().map_err(Into::into))?;
-                let name = parts
-                    .next()
-                    .ok_or_else(|| anyhow!("invalid input - wrong file format (name not found)"))?
-                    .to_owned();
-                fs.append_file(name, size)?;
-            }
-        } else {
-            return Err(anyhow!(
-                "wrong input - not listing, but input starts without $"
-            ));
-        }
-    }
-
-    Ok(fs.build())
+fn read_file(path: &str) -> Result<FileSystem> {
+    let file = File::open(path).map_err(|e| anyhow!("failed to open {}: {}", path, e))?;
+    transcript::read(BufReader::new(file))
 }
 
-fn dir_sizes(fs: &FileSystem) -> Result<Vec<usize>> {
-    let mut stack = vec![(0, 0, 0)];
-    let mut result = vec![];
-    let mut last_parent = usize::MAX;
-
-    while !stack.is_empty() {
-        let idx = stack.len() - 1;
-        // PANIC: We've just checked that stack is empty.
-        let (current, _, parent_idx) = stack.last_mut().copied().unwrap();
-        let coming_back = last_parent == current;
-
-        if !coming_back {
-            use FileSystemNode::*;
-
-            for (inode, child) in fs
-                .children(current)
-                .ok_or_else(|| anyhow!("attempt to traverse non-dir inode: {}", current))?
-            {
-                match child {
-                    File(_, size) => {
-                        stack[idx].1 += size;
-                    }
-                    Dir(..) => {
-                        stack.push((inode, 0, idx));
-                    }
-                }
-            }
-        }
+fn diff(old: &str, new: &str, args: &[&str]) -> Result<()> {
+    let differences = diff::diff(&read_file(old)?, &read_file(new)?)?;
 
-        if stack.last().unwrap().0 == current {
-            result.push(stack[idx].1);
-            stack[parent_idx].1 += stack[idx].1;
-            stack.pop();
-            if !stack.is_empty() {
-                last_parent = stack[parent_idx].0;
-            }
-        }
+    match args {
+        [] => print!("{}", diff::render_text(&differences)?),
+        ["--json"] => println!("{}", diff::render_json(&differences)),
+        _ => return Err(anyhow!("usage: diff <old> <new> [--json]")),
     }
 
-    Ok(result)
+    Ok(())
 }
 
 fn main() -> Result<()> {
-    let fs = read(BufReader::new(stdin()))?;
-
-    let sizes = dir_sizes(&fs)?;
-
-    let at_most_100000_sum = sizes
-        .iter()
-        .copied()
-        .filter(|size| *size <= 100000)
-        .sum::<usize>();
-
-    let total_used = sizes
-        .iter()
-        .copied()
-        .max()
-        .ok_or_else(|| anyhow!("expected at least one directory in input"))?;
-
-    let minimum_directory = sizes
-        .iter()
-        .copied()
-        .filter(|size| total_used.saturating_sub(*size) <= 40000000)
-        .min()
-        .ok_or_else(|| anyhow!("failed to find a directory matching conditions"))?;
-
-    println!(
-        "Total sum of directories with more than 100000 bytes is {}",
-        at_most_100000_sum,
-    );
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match args[..] {
+        ["validate", ref options @ ..] => return validate(options),
+        ["diff", old, new, ref options @ ..] => return diff(old, new, options),
+        ["import", root] => return materialize::import(Path::new(root), stdout().lock()),
+        ["repl", transcript] => {
+            return repl::run(&read_file(transcript)?, stdin().lock(), stdout().lock())
+        }
+        _ => {}
+    }
 
-    println!(
-        "Minimum directory that needs to be deleted to free up space has {} bytes",
-        minimum_directory
-    );
+    let fs = transcript::read(BufReader::new(stdin()))?;
 
-    Ok(())
+    match args[..] {
+        [] => answers(&fs),
+        ["query", operation, argument] => query(&fs, operation, argument),
+        ["tree", ref options @ ..] => tree(&fs, options),
+        ["plan", ref options @ ..] => plan(&fs, options),
+        ["export", root] => materialize::export(&fs, Path::new(root)),
+        _ => Err(anyhow!(
+            "usage: no-space-left-on-device [query resolve|ls|size|find <path> | tree [--sort name|size] [--depth N] [-h] | plan [--disk N] [--free N] | validate [--strict] | diff <old> <new> [--json] | repl <transcript> | export <root> | import <root>]"
+        )),
+    }
 }
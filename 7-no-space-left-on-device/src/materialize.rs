@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Result};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use crate::filesystem::{FileSystem, FileSystemNode};
+
+/// Recreates the filesystem under `root` as real directories and sparse files of recorded sizes.
+/// `root` must not exist yet.
+pub fn export(fs: &FileSystem, root: &Path) -> Result<()> {
+    if root.exists() {
+        return Err(anyhow!("{} already exists", root.display()));
+    }
+
+    fs::create_dir_all(root)?;
+
+    for (path, inode) in fs.paths() {
+        let node = fs
+            .get(inode)
+            .ok_or_else(|| anyhow!("inode {} does not exist", inode))?;
+        if matches!(node.name(), "." | "..") || node.name().contains(['/', '\\']) {
+            return Err(anyhow!("refusing to export {}, it escapes the root", path));
+        }
+
+        let target = root.join(path.trim_start_matches('/'));
+        match node {
+            FileSystemNode::Dir(..) => fs::create_dir(&target)?,
+            FileSystemNode::File(_, size) => File::create(&target)?.set_len(*size as u64)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Walks a real directory and writes a `$ cd`/`$ ls` transcript of it. Entries which are neither
+/// files nor directories, like symbolic links, are skipped.
+pub fn import(root: &Path, mut output: impl Write) -> Result<()> {
+    writeln!(output, "$ cd /")?;
+    import_dir(root, &mut output)
+}
+
+fn import_dir(dir: &Path, output: &mut impl Write) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| {
+            let entry = entry?;
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|name| anyhow!("name {:?} is not valid UTF-8", name))?;
+            if name.contains(char::is_whitespace) {
+                return Err(anyhow!("name {:?} can't be written in a transcript", name));
+            }
+
+            Ok((name, fs::symlink_metadata(entry.path())?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    writeln!(output, "$ ls")?;
+    for (name, metadata) in &entries {
+        if metadata.is_dir() {
+            writeln!(output, "dir {}", name)?;
+        } else if metadata.is_file() {
+            writeln!(output, "{} {}", metadata.len(), name)?;
+        }
+    }
+
+    for (name, metadata) in &entries {
+        if metadata.is_dir() {
+            writeln!(output, "$ cd {}", name)?;
+            import_dir(&dir.join(name), output)?;
+            writeln!(output, "$ cd ..")?;
+        }
+    }
+
+    Ok(())
+}
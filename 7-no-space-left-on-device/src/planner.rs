@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Result};
+
+use crate::filesystem::{node_sizes, FileSystem, FileSystemNode};
+
+/// Nodes to delete, none of them inside another one.
+pub struct DeletionPlan {
+    pub freed: usize,
+    pub nodes: Vec<(String, usize)>,
+}
+
+/// Chooses files and directories to delete, so at least `required_free` bytes are free on a
+/// disk of `disk_size` bytes, while removing as few bytes as possible.
+///
+/// Deleting a directory frees exactly as much as deleting every file inside of it, so this is
+/// a subset sum over files. Directories with all of their files chosen are reported instead
+/// of those files. Returns `None` if even deleting everything is not enough.
+pub fn plan_deletion(
+    fs: &FileSystem,
+    disk_size: usize,
+    required_free: usize,
+) -> Result<Option<DeletionPlan>> {
+    let (sizes, _) = node_sizes(fs)?;
+    let used = sizes[0];
+    let free = disk_size
+        .checked_sub(used)
+        .ok_or_else(|| anyhow!("{} bytes are used on a disk of {} bytes", used, disk_size))?;
+    let needed = required_free.saturating_sub(free);
+
+    let paths = fs.paths();
+    let files = paths
+        .iter()
+        .filter_map(|(_, inode)| match fs.get(*inode) {
+            Some(FileSystemNode::File(_, size)) if *size > 0 => Some((*inode, *size)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if used < needed {
+        return Ok(None);
+    }
+
+    // Deleting the smallest big enough node is always possible, so the best plan can't be
+    // bigger than that. Root is big enough, as everything together frees enough.
+    let upper_bound = paths
+        .iter()
+        .map(|(_, inode)| sizes[*inode])
+        .filter(|size| *size >= needed)
+        .min()
+        .unwrap_or(used);
+
+    let chosen = subset_sum(&files, needed, upper_bound);
+    let mut chosen_bytes = vec![0; sizes.len()];
+    for inode in &chosen {
+        chosen_bytes[*inode] = sizes[*inode];
+    }
+
+    // Paths are in depth-first order, so going backwards sums up children before parents.
+    for (_, inode) in paths.iter().rev() {
+        let subtree = fs
+            .children(*inode)
+            .into_iter()
+            .flatten()
+            .map(|(child, _)| chosen_bytes[child])
+            .sum::<usize>();
+        chosen_bytes[*inode] += subtree;
+    }
+
+    let mut nodes = vec![];
+    let mut skip_inside = None::<String>;
+    for (path, inode) in paths {
+        if let Some(prefix) = &skip_inside {
+            if path.starts_with(prefix.as_str()) {
+                continue;
+            }
+        }
+
+        if chosen_bytes[inode] > 0 && chosen_bytes[inode] == sizes[inode] {
+            skip_inside = Some(format!("{}/", path));
+            nodes.push((path, sizes[inode]));
+        }
+    }
+
+    Ok(Some(DeletionPlan {
+        freed: nodes.iter().map(|(_, size)| size).sum(),
+        nodes,
+    }))
+}
+
+const WORD: usize = u64::BITS as usize;
+
+/// Picks files which sum up to the smallest amount not less than `needed`, knowing that such
+/// sum is at most `upper_bound`. Returns picked inodes.
+fn subset_sum(files: &[(usize, usize)], needed: usize, upper_bound: usize) -> Vec<usize> {
+    let reachable = reachable_sums(files, upper_bound);
+    let sum = (needed..=upper_bound)
+        .find(|sum| contains(&reachable, *sum))
+        .unwrap_or(0);
+    drop(reachable);
+
+    let mut chosen = vec![];
+    pick(files, sum, &mut chosen);
+    chosen
+}
+
+/// Bitset of sums up to `limit` which some of `files` add up to.
+fn reachable_sums(files: &[(usize, usize)], limit: usize) -> Vec<u64> {
+    let mut reachable = vec![0u64; limit / WORD + 1];
+    reachable[0] = 1;
+
+    for (_, size) in files {
+        let (word_shift, bit_shift) = (size / WORD, size % WORD);
+
+        for word in (word_shift..reachable.len()).rev() {
+            let source = word - word_shift;
+            let mut shifted = reachable[source] << bit_shift;
+            if bit_shift > 0 && source > 0 {
+                shifted |= reachable[source - 1] >> (WORD - bit_shift);
+            }
+            reachable[word] |= shifted;
+        }
+    }
+
+    reachable
+}
+
+fn contains(reachable: &[u64], sum: usize) -> bool {
+    reachable[sum / WORD] & (1 << (sum % WORD)) != 0
+}
+
+/// Finds files adding up to `target`, which must be reachable. Instead of remembering how every
+/// sum was reached, both halves of `files` are solved separately and the target is split between
+/// them, so only a few bitsets of `target` bits are alive at a time. Every level of recursion
+/// costs at most as much as the initial pass, because targets of a level add up to `target`.
+fn pick(files: &[(usize, usize)], target: usize, chosen: &mut Vec<usize>) {
+    if target == 0 {
+        return;
+    }
+
+    if let [(inode, size)] = files {
+        debug_assert_eq!(*size, target);
+        chosen.push(*inode);
+        return;
+    }
+
+    let (left, right) = files.split_at(files.len() / 2);
+    let split = {
+        let left_sums = reachable_sums(left, target);
+        let right_sums = reachable_sums(right, target);
+
+        // PANIC: Target is reachable, so it is a sum of a left and a right part.
+        (0..=target)
+            .find(|a| contains(&left_sums, *a) && contains(&right_sums, target - a))
+            .unwrap()
+    };
+
+    pick(left, split, chosen);
+    pick(right, target - split, chosen);
+}
@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result};
+
+use crate::filesystem::{FileSystem, FileSystemNode};
+
+/// Path of a child node, given the path of its parent directory.
+pub fn child_path(parent: &str, name: &str) -> String {
+    if parent.ends_with('/') {
+        format!("{}{}", parent, name)
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+impl FileSystem {
+    /// Resolves a path to an inode. Absolute paths start at root, relative ones at `cwd`.
+    pub fn resolve_from(&self, cwd: usize, path: &str) -> Option<usize> {
+        let mut current = if path.starts_with('/') { 0 } else { cwd };
+
+        for part in path.split('/').filter(|part| !part.is_empty()) {
+            current = match part {
+                "." => current,
+                ".." => match self.get(current)? {
+                    FileSystemNode::Dir(_, _, parent) => *parent,
+                    FileSystemNode::File(..) => return None,
+                },
+                name => self
+                    .children(current)?
+                    .find(|(_, node)| node.name() == name)
+                    .map(|(inode, _)| inode)?,
+            };
+        }
+
+        Some(current)
+    }
+
+    pub fn resolve(&self, path: &str) -> Option<usize> {
+        self.resolve_from(0, path)
+    }
+
+    pub fn list(&self, path: &str) -> Result<Vec<(usize, &FileSystemNode)>> {
+        let inode = self
+            .resolve(path)
+            .ok_or_else(|| anyhow!("no such file or directory: {}", path))?;
+
+        Ok(self
+            .children(inode)
+            .ok_or_else(|| anyhow!("not a directory: {}", path))?
+            .collect())
+    }
+
+    /// Size of a file, or total size of everything inside a directory.
+    pub fn size(&self, inode: usize) -> usize {
+        let mut stack = vec![inode];
+        let mut total = 0;
+
+        while let Some(current) = stack.pop() {
+            match self.get(current) {
+                Some(FileSystemNode::File(_, size)) => total += size,
+                Some(FileSystemNode::Dir(_, contents, _)) => stack.extend(contents),
+                None => {}
+            }
+        }
+
+        total
+    }
+
+    /// Absolute paths of all nodes except root, in depth-first order.
+    pub fn paths(&self) -> Vec<(String, usize)> {
+        let mut stack = vec![("/".to_owned(), 0)];
+        let mut result = vec![];
+
+        while let Some((path, inode)) = stack.pop() {
+            if let Some(children) = self.children(inode) {
+                let mut children = children
+                    .map(|(child, node)| (child_path(&path, node.name()), child))
+                    .collect::<Vec<_>>();
+                children.reverse();
+                stack.extend(children);
+            }
+
+            if inode != 0 {
+                result.push((path, inode));
+            }
+        }
+
+        result
+    }
+
+    /// Nodes with paths matching a glob pattern. `*` and `?` match inside a single path
+    /// segment, `**` matches any number of segments.
+    pub fn find(&self, pattern: &str) -> Vec<(String, usize)> {
+        let pattern = pattern
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+
+        self.paths()
+            .into_iter()
+            .filter(|(path, _)| {
+                let path = path
+                    .split('/')
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>();
+                glob_match(&pattern, &path)
+            })
+            .collect()
+    }
+}
+
+fn glob_match(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            glob_match(&pattern[1..], path) || (!path.is_empty() && glob_match(pattern, &path[1..]))
+        }
+        (Some(segment), Some(name)) => {
+            let segment = segment.chars().collect::<Vec<_>>();
+            let name = name.chars().collect::<Vec<_>>();
+            segment_match(&segment, &name) && glob_match(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn segment_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            segment_match(&pattern[1..], name)
+                || (!name.is_empty() && segment_match(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => segment_match(&pattern[1..], &name[1..]),
+        (Some(expected), Some(actual)) => {
+            expected == actual && segment_match(&pattern[1..], &name[1..])
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use std::fmt::Write;
+
+use crate::filesystem::{node_sizes, FileSystem, FileSystemNode};
+
+#[derive(Clone, Copy)]
+pub enum SortBy {
+    /// Order in which nodes appeared in the transcript.
+    Listing,
+    Name,
+    /// Biggest first.
+    Size,
+}
+
+pub struct TreeOptions {
+    pub sort: SortBy,
+    pub max_depth: Option<usize>,
+    pub human_readable: bool,
+}
+
+/// Formats a size with binary units, like `du -h` does.
+pub fn human_size(size: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if size < 1024 {
+        return format!("{}B", size);
+    }
+
+    let mut scaled = size as f64 / 1024.0;
+    let mut unit = 0;
+    while scaled >= 1024.0 && unit + 1 < UNITS.len() {
+        scaled /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1}{}", scaled, UNITS[unit])
+}
+
+/// Renders the tree in the same format the puzzle uses, with recursive totals for directories.
+/// Totals come from `node_sizes`, so they are the same as in `dir_sizes`.
+pub fn render_tree(fs: &FileSystem, options: &TreeOptions) -> Result<String> {
+    let (sizes, _) = node_sizes(fs)?;
+    let format_size = |size: usize| {
+        if options.human_readable {
+            human_size(size)
+        } else {
+            size.to_string()
+        }
+    };
+
+    let mut result = String::new();
+    let mut stack = vec![(0, 0)];
+
+    while let Some((inode, depth)) = stack.pop() {
+        let indent = "  ".repeat(depth);
+
+        match fs.get(inode) {
+            Some(FileSystemNode::File(name, size)) => {
+                writeln!(
+                    result,
+                    "{}- {} (file, size={})",
+                    indent,
+                    name,
+                    format_size(*size)
+                )?;
+            }
+            Some(FileSystemNode::Dir(name, ..)) => {
+                let name = if inode == 0 { "/" } else { name };
+                writeln!(
+                    result,
+                    "{}- {} (dir, total={})",
+                    indent,
+                    name,
+                    format_size(sizes[inode])
+                )?;
+
+                if options.max_depth.is_none_or(|max_depth| depth < max_depth) {
+                    let mut children = fs.children(inode).into_iter().flatten().collect::<Vec<_>>();
+
+                    match options.sort {
+                        SortBy::Listing => {}
+                        SortBy::Name => children.sort_by(|(_, a), (_, b)| a.name().cmp(b.name())),
+                        SortBy::Size => {
+                            children.sort_by_key(|(child, _)| std::cmp::Reverse(sizes[*child]))
+                        }
+                    }
+
+                    stack.extend(
+                        children
+                            .into_iter()
+                            .rev()
+                            .map(|(child, _)| (child, depth + 1)),
+                    );
+                }
+            }
+            None => {}
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,153 @@
+use anyhow::{anyhow, Result};
+use std::io::{BufRead, Write};
+
+use crate::{
+    filesystem::{node_sizes, FileSystem, FileSystemNode},
+    query::child_path,
+};
+
+const HELP: &str =
+    "commands: cd <path>, ls [path], pwd, du [path], find <pattern>, stat <path>, help, exit";
+
+struct Shell<'fs> {
+    fs: &'fs FileSystem,
+    sizes: Vec<usize>,
+    cwd: usize,
+}
+
+impl<'fs> Shell<'fs> {
+    fn resolve(&self, path: &str) -> Result<usize> {
+        self.fs
+            .resolve_from(self.cwd, path)
+            .ok_or_else(|| anyhow!("{}: no such file or directory", path))
+    }
+
+    fn node(&self, inode: usize) -> Result<&'fs FileSystemNode> {
+        self.fs
+            .get(inode)
+            .ok_or_else(|| anyhow!("inode {} does not exist", inode))
+    }
+
+    /// Absolute path of a directory, built by following parents up to root.
+    fn dir_path(&self, dir: usize) -> Result<String> {
+        let mut names = vec![];
+        let mut current = dir;
+
+        while current != 0 {
+            match self.node(current)? {
+                FileSystemNode::Dir(name, _, parent) => {
+                    names.push(name.as_str());
+                    current = *parent;
+                }
+                FileSystemNode::File(..) => return Err(anyhow!("inode {} is a file", current)),
+            }
+        }
+
+        names.reverse();
+        Ok(format!("/{}", names.join("/")))
+    }
+
+    fn execute(&mut self, line: &str, output: &mut impl Write) -> Result<()> {
+        let mut parts = line.split_whitespace();
+        let command = parts.next().unwrap_or("");
+        let argument = parts.next();
+
+        match (command, argument) {
+            ("", _) => {}
+            ("help", _) => writeln!(output, "{}", HELP)?,
+            ("pwd", _) => writeln!(output, "{}", self.dir_path(self.cwd)?)?,
+            ("cd", path) => {
+                let target = self.resolve(path.unwrap_or("/"))?;
+                if !self.node(target)?.is_dir() {
+                    return Err(anyhow!("{}: not a directory", path.unwrap_or("/")));
+                }
+                self.cwd = target;
+            }
+            ("ls", path) => {
+                let dir = self.resolve(path.unwrap_or("."))?;
+                let children = self
+                    .fs
+                    .children(dir)
+                    .ok_or_else(|| anyhow!("{}: not a directory", path.unwrap_or(".")))?;
+
+                for (_, node) in children {
+                    match node {
+                        FileSystemNode::File(name, size) => writeln!(output, "{} {}", size, name)?,
+                        FileSystemNode::Dir(name, ..) => writeln!(output, "dir {}", name)?,
+                    }
+                }
+            }
+            ("du", path) => {
+                let inode = self.resolve(path.unwrap_or("."))?;
+                writeln!(output, "{}", self.sizes[inode])?;
+            }
+            ("find", Some(pattern)) => {
+                let pattern = if pattern.starts_with('/') {
+                    pattern.to_owned()
+                } else {
+                    child_path(&self.dir_path(self.cwd)?, pattern)
+                };
+
+                for (path, inode) in self.fs.find(&pattern) {
+                    writeln!(output, "{} {}", self.sizes[inode], path)?;
+                }
+            }
+            ("stat", Some(path)) => {
+                let inode = self.resolve(path)?;
+                match self.node(inode)? {
+                    FileSystemNode::File(name, size) => writeln!(
+                        output,
+                        "name: {}\ntype: file\ninode: {}\nsize: {}",
+                        name, inode, size
+                    )?,
+                    FileSystemNode::Dir(name, contents, _) => writeln!(
+                        output,
+                        "name: {}\ntype: dir\ninode: {}\nsize: {}\npath: {}\nentries: {}",
+                        if inode == 0 { "/" } else { name },
+                        inode,
+                        self.sizes[inode],
+                        self.dir_path(inode)?,
+                        contents.len()
+                    )?,
+                }
+            }
+            (command @ ("find" | "stat"), None) => {
+                return Err(anyhow!("{}: missing argument", command))
+            }
+            (command, _) => return Err(anyhow!("{}: unknown command, try help", command)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs an interactive shell over the filesystem until input ends or `exit` is entered.
+/// Errors of single commands are printed and don't stop the shell.
+pub fn run(fs: &FileSystem, input: impl BufRead, mut output: impl Write) -> Result<()> {
+    let (sizes, _) = node_sizes(fs)?;
+    let mut shell = Shell { fs, sizes, cwd: 0 };
+    let mut lines = input.lines();
+
+    loop {
+        write!(output, "{}> ", shell.dir_path(shell.cwd)?)?;
+        output.flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => {
+                writeln!(output)?;
+                break;
+            }
+        };
+
+        if matches!(line.trim(), "exit" | "quit") {
+            break;
+        }
+
+        if let Err(e) = shell.execute(&line, &mut output) {
+            writeln!(output, "error: {}", e)?;
+        }
+    }
+
+    Ok(())
+}
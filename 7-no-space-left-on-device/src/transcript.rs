@@ -0,0 +1,371 @@
+use anyhow::{anyhow, Result};
+use std::{collections::HashSet, io::BufRead};
+
+use crate::{
+    filesystem::{FileSystem, FileSystemNode},
+    validation::{Issue, IssueKind, Validation},
+};
+
+enum Command<'line> {
+    ChangeDir(&'line str),
+    List,
+    MakeDir(&'line str),
+    Remove(&'line str, bool),
+    Move(&'line str, &'line str),
+    Touch(&'line str),
+}
+
+fn read_command(line: &str) -> Option<Command<'_>> {
+    use Command::*;
+
+    if let Some(target) = line.strip_prefix("cd ") {
+        Some(ChangeDir(target))
+    } else if line == "ls" {
+        Some(List)
+    } else if let Some(path) = line.strip_prefix("mkdir ") {
+        Some(MakeDir(path))
+    } else if let Some(path) = line.strip_prefix("rm -r ") {
+        Some(Remove(path, true))
+    } else if let Some(path) = line.strip_prefix("rm ") {
+        Some(Remove(path, false))
+    } else if let Some(paths) = line.strip_prefix("mv ") {
+        paths.split_once(' ').map(|(from, to)| Move(from, to))
+    } else {
+        line.strip_prefix("touch ").map(Touch)
+    }
+}
+
+struct FileSystemBuilder {
+    fs: FileSystem,
+    cwd: usize,
+    validation: Validation,
+    issues: Vec<Issue>,
+    line: usize,
+    listing: HashSet<String>,
+    /// Files created by `touch`, whose size is not known until they are listed.
+    touched: HashSet<usize>,
+}
+
+impl FileSystemBuilder {
+    fn new(validation: Validation) -> Self {
+        use FileSystemNode::*;
+
+        Self {
+            fs: FileSystem(vec![Some(Dir("".into(), vec![], 0))]),
+            cwd: 0,
+            validation,
+            issues: vec![],
+            line: 0,
+            listing: HashSet::new(),
+            touched: HashSet::new(),
+        }
+    }
+
+    fn report(&mut self, kind: IssueKind) -> Result<()> {
+        let issue = Issue {
+            line: self.line,
+            kind,
+        };
+
+        match self.validation {
+            Validation::Off => Ok(()),
+            Validation::Report => {
+                self.issues.push(issue);
+                Ok(())
+            }
+            Validation::Strict => Err(issue.into()),
+        }
+    }
+
+    fn start_listing(&mut self) {
+        self.listing.clear();
+    }
+
+    fn list_entry(&mut self, name: &str) -> Result<()> {
+        if !self.listing.insert(name.to_owned()) {
+            self.report(IssueKind::DuplicateListing(name.to_owned()))?;
+        }
+
+        Ok(())
+    }
+
+    fn cwd_dir(&self) -> Result<&FileSystemNode> {
+        self.fs
+            .get(self.cwd)
+            .ok_or_else(|| anyhow!("cwd is wrong, invalid index"))
+            .and_then(|node| {
+                node.is_dir()
+                    .then_some(node)
+                    .ok_or_else(|| anyhow!("cwd is wrong, not a directory"))
+            })
+    }
+
+    fn cwd_parent(&self) -> Result<usize> {
+        if let FileSystemNode::Dir(_, _, parent) = self.cwd_dir()? {
+            Ok(*parent)
+        } else {
+            panic!("cwd_dir logic is wrong and it returned a non-dir node");
+        }
+    }
+
+    fn find_or_create_dir(&mut self, inner: &str) -> Result<usize> {
+        match self.find_node(inner)? {
+            Some(inode) => Ok(inode),
+            None => self.fs.insert(
+                self.cwd,
+                FileSystemNode::Dir(inner.to_owned(), vec![], self.cwd),
+            ),
+        }
+    }
+
+    fn change_dir(&mut self, target: &str) -> Result<()> {
+        if target.starts_with('/') {
+            self.cwd = 0;
+        }
+
+        for part in target.split('/').filter(|part| !part.is_empty()) {
+            match part {
+                ".." => {
+                    if self.cwd == 0 {
+                        self.report(IssueKind::AboveRoot)?;
+                    }
+                    self.cwd = self.cwd_parent()?;
+                }
+                "." => {}
+                inner => {
+                    if self.find_node(inner)?.is_none() {
+                        self.report(IssueKind::UnlistedDir(inner.to_owned()))?;
+                    }
+                    self.cwd = self.find_or_create_dir(inner)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn find_node(&self, name: &str) -> Result<Option<usize>> {
+        Ok(self
+            .fs
+            .children(self.cwd)
+            .into_iter()
+            .flatten()
+            .find(|(_, node)| node.name() == name)
+            .map(|(inode, _)| inode))
+    }
+
+    fn append_file(&mut self, name: String, size: usize) -> Result<usize> {
+        match self.find_node(&name)? {
+            Some(inode) if self.touched.remove(&inode) => {
+                if let Some(FileSystemNode::File(_, previous)) = self.fs.get_mut(inode) {
+                    *previous = size;
+                }
+
+                Ok(inode)
+            }
+            Some(inode) => {
+                if let Some(FileSystemNode::File(_, previous)) = self.fs.get(inode) {
+                    if *previous != size {
+                        let previous = *previous;
+                        self.report(IssueKind::SizeMismatch {
+                            name,
+                            previous,
+                            listed: size,
+                        })?;
+                    }
+                }
+
+                Ok(inode)
+            }
+            None => self.fs.insert(self.cwd, FileSystemNode::File(name, size)),
+        }
+    }
+
+    fn find_child(&self, dir: usize, name: &str) -> Option<usize> {
+        self.fs
+            .children(dir)?
+            .find(|(_, node)| node.name() == name)
+            .map(|(inode, _)| inode)
+    }
+
+    /// Splits a path into its parent directory and the last name in it.
+    fn resolve_parent<'p>(&self, path: &'p str) -> Result<(usize, &'p str)> {
+        let trimmed = path.trim_end_matches('/');
+        let (parent, name) = match trimmed.rsplit_once('/') {
+            Some(("", name)) => (Some(0), name),
+            Some((parent, name)) => (self.fs.resolve_from(self.cwd, parent), name),
+            None => (Some(self.cwd), trimmed),
+        };
+
+        if matches!(name, "" | "." | "..") {
+            return Err(anyhow!("invalid path: {}", path));
+        }
+
+        parent
+            .filter(|parent| self.fs.get(*parent).is_some_and(FileSystemNode::is_dir))
+            .map(|parent| (parent, name))
+            .ok_or_else(|| anyhow!("no such directory: {}", path))
+    }
+
+    fn make_dir(&mut self, path: &str) -> Result<usize> {
+        let (parent, name) = self.resolve_parent(path)?;
+        if self.find_child(parent, name).is_some() {
+            return Err(anyhow!("cannot create directory {}: already exists", path));
+        }
+
+        self.fs
+            .insert(parent, FileSystemNode::Dir(name.to_owned(), vec![], parent))
+    }
+
+    fn touch(&mut self, path: &str) -> Result<usize> {
+        let (parent, name) = self.resolve_parent(path)?;
+
+        match self.find_child(parent, name) {
+            Some(inode) => Ok(inode),
+            None => {
+                let inode = self
+                    .fs
+                    .insert(parent, FileSystemNode::File(name.to_owned(), 0))?;
+                self.touched.insert(inode);
+                Ok(inode)
+            }
+        }
+    }
+
+    fn remove(&mut self, path: &str, recursive: bool) -> Result<()> {
+        let (parent, name) = self.resolve_parent(path)?;
+        let inode = self
+            .find_child(parent, name)
+            .ok_or_else(|| anyhow!("cannot remove {}: no such file or directory", path))?;
+
+        if self.fs.get(inode).is_some_and(FileSystemNode::is_dir) {
+            if !recursive {
+                return Err(anyhow!("cannot remove {}: is a directory", path));
+            }
+
+            if self.fs.is_within(self.cwd, inode) {
+                return Err(anyhow!(
+                    "cannot remove {}: current directory is inside",
+                    path
+                ));
+            }
+        }
+
+        self.fs.remove(parent, inode);
+        Ok(())
+    }
+
+    fn move_node(&mut self, from: &str, to: &str) -> Result<()> {
+        let (from_parent, from_name) = self.resolve_parent(from)?;
+        let inode = self
+            .find_child(from_parent, from_name)
+            .ok_or_else(|| anyhow!("cannot move {}: no such file or directory", from))?;
+        let is_dir = self.fs.get(inode).is_some_and(FileSystemNode::is_dir);
+
+        let (to_parent, to_name) = match self.fs.resolve_from(self.cwd, to) {
+            Some(target) if self.fs.get(target).is_some_and(FileSystemNode::is_dir) => {
+                (target, from_name)
+            }
+            _ => self.resolve_parent(to)?,
+        };
+
+        if is_dir && self.fs.is_within(to_parent, inode) {
+            return Err(anyhow!("cannot move {} into itself", from));
+        }
+
+        match self.find_child(to_parent, to_name) {
+            Some(existing) if existing == inode => return Ok(()),
+            Some(existing) => {
+                if is_dir || self.fs.get(existing).is_some_and(FileSystemNode::is_dir) {
+                    return Err(anyhow!("cannot move {} to {}: target exists", from, to));
+                }
+
+                self.fs.remove(to_parent, existing);
+            }
+            None => {}
+        }
+
+        self.fs.relocate(inode, from_parent, to_parent, to_name)
+    }
+
+    fn build(self) -> (FileSystem, Vec<Issue>) {
+        (self.fs, self.issues)
+    }
+}
+
+pub fn read(reader: impl BufRead) -> Result<FileSystem> {
+    read_validated(reader, Validation::Off).map(|(fs, _)| fs)
+}
+
+/// Reads a transcript, checking it for inconsistencies according to `validation`.
+pub fn read_validated(
+    reader: impl BufRead,
+    validation: Validation,
+) -> Result<(FileSystem, Vec<Issue>)> {
+    let mut fs = FileSystemBuilder::new(validation);
+
+    let mut is_listing = false;
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        fs.line = idx + 1;
+
+        if let Some(command) = line.strip_prefix('$') {
+            is_listing = false;
+            let command = command
+                .strip_prefix(' ')
+                .and_then(read_command)
+                .ok_or_else(|| anyhow!("line {}: invalid command: {}", fs.line, line))?;
+
+            use Command::*;
+            match command {
+                ChangeDir(target) => {
+                    fs.change_dir(target)?;
+                }
+                List => {
+                    is_listing = true;
+                    fs.start_listing();
+                }
+                MakeDir(path) => {
+                    fs.make_dir(path)?;
+                }
+                Remove(path, recursive) => {
+                    fs.remove(path, recursive)?;
+                }
+                Move(from, to) => {
+                    fs.move_node(from, to)?;
+                }
+                Touch(path) => {
+                    fs.touch(path)?;
+                }
+            }
+        } else if is_listing {
+            if let Some(name) = line.strip_prefix("dir ").filter(|name| !name.is_empty()) {
+                fs.list_entry(name)?;
+                fs.find_or_create_dir(name)?;
+            } else {
+                let mut parts = line.split_whitespace();
+                let size = parts
+                    .next()
+                    .ok_or_else(|| anyhow!("line {}: empty listing entry", fs.line))
+                    .and_then(|size| {
+                        size.parse::<usize>().map_err(|e| {
+                            anyhow!("line {}: invalid listing entry {}: {}", fs.line, line, e)
+                        })
+                    })?;
+                let name = parts
+                    .next()
+                    .ok_or_else(|| anyhow!("line {}: file name not found: {}", fs.line, line))?
+                    .to_owned();
+                fs.list_entry(&name)?;
+                fs.append_file(name, size)?;
+            }
+        } else {
+            return Err(anyhow!(
+                "line {}: not listing, but input starts without $",
+                fs.line
+            ));
+        }
+    }
+
+    Ok(fs.build())
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Validation {
+    /// Inconsistencies are silently accepted.
+    Off,
+    /// Inconsistencies are collected and returned together with the filesystem.
+    Report,
+    /// Building fails on the first inconsistency.
+    Strict,
+}
+
+#[derive(Debug)]
+pub enum IssueKind {
+    SizeMismatch {
+        name: String,
+        previous: usize,
+        listed: usize,
+    },
+    UnlistedDir(String),
+    DuplicateListing(String),
+    AboveRoot,
+}
+
+/// Inconsistency found in a transcript, with its line number.
+#[derive(Debug)]
+pub struct Issue {
+    pub line: usize,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use IssueKind::*;
+
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            SizeMismatch {
+                name,
+                previous,
+                listed,
+            } => write!(
+                f,
+                "file {} listed with {} bytes, but it had {} bytes before",
+                name, listed, previous
+            ),
+            UnlistedDir(name) => {
+                write!(f, "changed into directory {} which was never listed", name)
+            }
+            DuplicateListing(name) => write!(f, "{} is listed more than once", name),
+            AboveRoot => write!(f, "changed into parent directory of root"),
+        }
+    }
+}
+
+impl std::error::Error for Issue {}
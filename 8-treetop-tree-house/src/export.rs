@@ -0,0 +1,170 @@
+use std::{fmt::Write as _, io::Write};
+
+use anyhow::Result;
+
+use crate::{ScenicMap, TreeMap, VisibilityMap};
+
+/// Colour used for the tree with the best scenic score.
+const HIGHLIGHT: [u8; 3] = [255, 0, 255];
+
+/// Image with one (grey) or three (RGB) 8-bit channels per pixel.
+pub struct Raster {
+    width: usize,
+    height: usize,
+    channels: usize,
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    fn grey(map: &TreeMap, value: impl Fn((usize, usize)) -> u8) -> Self {
+        let pixels = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .map(value)
+            .collect();
+
+        Self {
+            width: map.width,
+            height: map.height,
+            channels: 1,
+            pixels,
+        }
+    }
+
+    pub fn heights(map: &TreeMap) -> Self {
+        let highest = map
+            .trees
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        Self::grey(map, |(x, y)| {
+            (map.trees[y][x] as usize * 255 / highest as usize) as u8
+        })
+    }
+
+    /// Visible trees in white, the viewer (if any) in highlight colour.
+    pub fn visibility(
+        map: &TreeMap,
+        visibility_map: &VisibilityMap,
+        viewer: Option<(usize, usize)>,
+    ) -> Self {
+        let mut result = Self::grey(map, |pt| {
+            if visibility_map.is_visible(&pt) {
+                255
+            } else {
+                0
+            }
+        });
+
+        if let Some((x, y)) = viewer {
+            result = result.to_rgb();
+            let idx = 3 * (y * result.width + x);
+            result.pixels[idx..idx + 3].copy_from_slice(&HIGHLIGHT);
+        }
+
+        result
+    }
+
+    fn to_rgb(&self) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            channels: 3,
+            pixels: self.pixels.iter().flat_map(|grey| [*grey; 3]).collect(),
+        }
+    }
+
+    /// Scores on a colour ramp. Scores grow multiplicatively, so the ramp is logarithmic.
+    pub fn scores(map: &TreeMap, scenic_map: &ScenicMap) -> Self {
+        let best = scenic_map.best();
+        let top = best.map_or(1.0, |(_, score)| (score as f64).ln_1p().max(f64::EPSILON));
+
+        let pixels = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .flat_map(|pt| {
+                if best.is_some_and(|(best, _)| best == pt) {
+                    HIGHLIGHT
+                } else {
+                    ramp((scenic_map.score(&pt) as f64).ln_1p() / top)
+                }
+            })
+            .collect();
+
+        Self {
+            width: map.width,
+            height: map.height,
+            channels: 3,
+            pixels,
+        }
+    }
+
+    /// Writes the image as PGM (grey) or PPM (RGB), either in plain text or binary variant.
+    pub fn write_netpbm(&self, mut output: impl Write, binary: bool) -> Result<()> {
+        let magic = match (self.channels, binary) {
+            (1, false) => "P2",
+            (1, true) => "P5",
+            (_, false) => "P3",
+            (_, true) => "P6",
+        };
+        writeln!(output, "{}\n{} {}\n255", magic, self.width, self.height)?;
+
+        if binary {
+            output.write_all(&self.pixels)?;
+        } else {
+            // Plain format should not have lines longer than 70 characters.
+            for row in self.pixels.chunks(self.width * self.channels) {
+                for line in row.chunks(17) {
+                    let values = line.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    writeln!(output, "{}", values.join(" "))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders the image for a terminal with true colour support, two cells per pixel.
+    pub fn ansi(&self) -> String {
+        let mut result = String::new();
+
+        for row in self.pixels.chunks(self.width * self.channels) {
+            for pixel in row.chunks(self.channels) {
+                let [r, g, b] = match pixel {
+                    [grey] => [*grey; 3],
+                    [r, g, b] => [*r, *g, *b],
+                    _ => unreachable!("raster has one or three channels"),
+                };
+                // PANIC: Writing to a String never fails.
+                write!(result, "\x1b[48;2;{};{};{}m  ", r, g, b).unwrap();
+            }
+            result.push_str("\x1b[0m\n");
+        }
+
+        result
+    }
+}
+
+/// Maps `t` from 0 to 1 onto dark blue, teal, green and yellow.
+fn ramp(t: f64) -> [u8; 3] {
+    const STOPS: [[f64; 3]; 4] = [
+        [68.0, 1.0, 84.0],
+        [49.0, 104.0, 142.0],
+        [53.0, 183.0, 121.0],
+        [253.0, 231.0, 37.0],
+    ];
+
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let idx = (position as usize).min(STOPS.len() - 2);
+    let fraction = position - idx as f64;
+
+    let mut result = [0; 3];
+    for (channel, value) in result.iter_mut().enumerate() {
+        let (from, to) = (STOPS[idx][channel], STOPS[idx + 1][channel]);
+        *value = (from + (to - from) * fraction).round() as u8;
+    }
+
+    result
+}
@@ -1,9 +1,62 @@
 use anyhow::{anyhow, Error, Result};
 use std::{
-    io::{stdin, BufReader, Read},
+    fs::File,
+    io::{stdin, BufReader, BufWriter, Read},
     str::FromStr,
 };
 
+mod export;
+mod sight;
+
+use export::Raster;
+use sight::VisibilityMap;
+
+#[derive(Default)]
+struct Options {
+    heights: Option<String>,
+    visibility: Option<String>,
+    scores: Option<String>,
+    binary: bool,
+    ansi: Option<String>,
+    diagonal: bool,
+    viewer: Option<(usize, usize)>,
+    alphabet: Alphabet,
+}
+
+impl Options {
+    fn from_args() -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("{} expects a value", arg))
+            };
+
+            match arg.as_str() {
+                "--heights" => options.heights = Some(value()?),
+                "--visibility" => options.visibility = Some(value()?),
+                "--scores" => options.scores = Some(value()?),
+                "--ansi" => options.ansi = Some(value()?),
+                "--binary" => options.binary = true,
+                "--diagonal" => options.diagonal = true,
+                "--base36" => options.alphabet = Alphabet::Base36,
+                "--viewer" => {
+                    let viewer = value()?;
+                    let (x, y) = viewer
+                        .split_once(',')
+                        .ok_or_else(|| anyhow!("--viewer expects a position as x,y"))?;
+                    options.viewer = Some((x.parse()?, y.parse()?));
+                }
+                _ => return Err(anyhow!("unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
 #[derive(Debug)]
 struct TreeMap {
     width: usize,
@@ -11,29 +64,68 @@ struct TreeMap {
     trees: Vec<Vec<u8>>,
 }
 
-impl FromStr for TreeMap {
-    type Err = Error;
+/// Characters used for tree heights.
+#[derive(Debug, Copy, Clone, Default)]
+enum Alphabet {
+    /// Heights from `0` to `9`.
+    #[default]
+    Digits,
+    /// Heights from `0` to `9` followed by `a` to `z`, 36 levels in total.
+    Base36,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Alphabet {
+    fn height(self, c: char) -> Option<u8> {
+        match self {
+            Alphabet::Digits => c.to_digit(10),
+            Alphabet::Base36 if c.is_ascii_uppercase() => None,
+            Alphabet::Base36 => c.to_digit(36),
+        }
+        .map(|v| v as u8)
+    }
+}
+
+impl TreeMap {
+    fn parse(s: &str, alphabet: Alphabet) -> Result<Self> {
         let trees = s
-            .lines()
-            .map(|line| {
-                line.chars()
-                    .map(|digit| {
-                        digit
-                            .to_digit(10)
-                            .map(|v| v as u8)
-                            .ok_or_else(|| anyhow!("failed to parse digit {}", digit))
+            .trim_end_matches(['\r', '\n'])
+            .split('\n')
+            .enumerate()
+            .map(|(row, line)| {
+                line.trim_end_matches('\r')
+                    .chars()
+                    .enumerate()
+                    .map(|(column, c)| {
+                        alphabet.height(c).ok_or_else(|| {
+                            anyhow!(
+                                "row {}, column {}: invalid tree height {:?}",
+                                row + 1,
+                                column + 1,
+                                c
+                            )
+                        })
                     })
                     .collect::<Result<Vec<u8>>>()
             })
             .collect::<Result<Vec<_>>>()?;
 
-        if trees.is_empty() {
+        let (width, height) = (trees[0].len(), trees.len());
+        if width == 0 {
             return Err(anyhow!("tree map is empty"));
         }
 
-        let (width, height) = (trees[0].len(), trees.len());
+        if let Some((row, trees)) = trees
+            .iter()
+            .enumerate()
+            .find(|(_, trees)| trees.len() != width)
+        {
+            return Err(anyhow!(
+                "row {} has {} trees, expected {} as in the first row",
+                row + 1,
+                trees.len(),
+                width
+            ));
+        }
 
         Ok(Self {
             trees,
@@ -43,6 +135,16 @@ impl FromStr for TreeMap {
     }
 }
 
+impl FromStr for TreeMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, Alphabet::default())
+    }
+}
+
+/// Reference implementation of visibility from outside along the axes.
+#[cfg(test)]
 #[derive(Debug, Copy, Clone, Default)]
 struct OcclusionPoint {
     top: u8,
@@ -51,8 +153,10 @@ struct OcclusionPoint {
     bottom: u8,
 }
 
+#[cfg(test)]
 struct OcclusionMap(Vec<Vec<OcclusionPoint>>);
 
+#[cfg(test)]
 impl OcclusionMap {
     fn build(map: &TreeMap) -> Self {
         let mut result = vec![vec![OcclusionPoint::default(); map.width]; map.height];
@@ -107,7 +211,104 @@ impl OcclusionMap {
     }
 }
 
+/// Directions in which trees can be seen from a tree house.
+const AXES: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Axes together with diagonals.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// Scenic score of every tree, computed in one monotonic stack sweep per direction.
+struct ScenicMap(Vec<Vec<usize>>);
+
+impl ScenicMap {
+    fn build(map: &TreeMap, directions: &[(isize, isize)]) -> Self {
+        let mut result = vec![vec![1; map.width]; map.height];
+
+        for direction in directions {
+            let distances = map.viewing_distances(*direction);
+
+            for (scores, distances) in result.iter_mut().zip(distances) {
+                for (score, distance) in scores.iter_mut().zip(distances) {
+                    *score *= distance;
+                }
+            }
+        }
+
+        Self(result)
+    }
+
+    fn score(&self, &(x, y): &(usize, usize)) -> usize {
+        self.0[y][x]
+    }
+
+    fn best(&self) -> Option<((usize, usize), usize)> {
+        self.0
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, score)| ((x, y), *score))
+            })
+            .max_by_key(|(_, score)| *score)
+    }
+}
+
 impl TreeMap {
+    fn contains(&self, (x, y): (isize, isize)) -> bool {
+        (0..self.width as isize).contains(&x) && (0..self.height as isize).contains(&y)
+    }
+
+    /// Far ends of all lines running towards `(dx, dy)`.
+    fn line_starts(&self, (dx, dy): (isize, isize)) -> impl Iterator<Item = (isize, isize)> + '_ {
+        (0..self.height as isize)
+            .flat_map(|y| (0..self.width as isize).map(move |x| (x, y)))
+            .filter(move |(x, y)| !self.contains((x + dx, y + dy)))
+    }
+
+    /// For every tree, number of trees seen when looking towards `(dx, dy)`. Every line is walked
+    /// from its far end with a stack of trees not yet hidden behind a taller one.
+    fn viewing_distances(&self, (dx, dy): (isize, isize)) -> Vec<Vec<usize>> {
+        let mut result = vec![vec![0; self.width]; self.height];
+
+        for (mut x, mut y) in self.line_starts((dx, dy)) {
+            let mut stack: Vec<(usize, u8)> = vec![];
+
+            for steps in 0.. {
+                if !self.contains((x, y)) {
+                    break;
+                }
+
+                let length = self.trees[y as usize][x as usize];
+                while stack.last().is_some_and(|(_, top)| *top < length) {
+                    stack.pop();
+                }
+
+                result[y as usize][x as usize] = match stack.last() {
+                    Some((blocker, _)) => steps - blocker,
+                    None => steps,
+                };
+                stack.push((steps, length));
+
+                x -= dx;
+                y -= dy;
+            }
+        }
+
+        result
+    }
+
+    /// Reference implementation, walking outwards from a single tree.
+    #[cfg(test)]
     fn scenic_score(&self, (x, y): (usize, usize)) -> usize {
         let length = self.trees[y][x];
 
@@ -137,27 +338,107 @@ impl TreeMap {
 }
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?;
     let mut input = String::new();
     BufReader::new(stdin()).read_to_string(&mut input)?;
-    let input: TreeMap = input.parse()?;
-    let occlusion_map = OcclusionMap::build(&input);
+    let input = TreeMap::parse(&input, options.alphabet)?;
+    let directions = if options.diagonal {
+        &DIRECTIONS[..]
+    } else {
+        &AXES[..]
+    };
+    let visibility_map = VisibilityMap::from_outside(&input, directions);
+    let scenic_map = ScenicMap::build(&input, directions);
+
+    let visible_trees = visibility_map.count();
+
+    let viewer_map = match options.viewer {
+        Some((x, y)) if x < input.width && y < input.height => {
+            Some(VisibilityMap::from_viewer(&input, (x, y)))
+        }
+        Some((x, y)) => return Err(anyhow!("no tree at {},{}", x, y)),
+        None => None,
+    };
 
-    let visible_trees = (0..input.height)
-        .into_iter()
-        .flat_map(|y| (0..input.width).into_iter().map(move |x| (x, y)))
-        .filter(|pt| occlusion_map.is_visible(&input, pt))
-        .count();
+    let (_, maximum_scenic_score) = scenic_map.best().ok_or_else(|| anyhow!("empty input"))?;
 
-     let maximum_scenic_score = (0..input.height)
-         .into_iter()
-         .flat_map(|y| (0..input.width).into_iter().map(move |x| (x, y)))
-         .map(|pt| input.scenic_score(pt))
-         .max()
-         .ok_or_else(|| anyhow!("empty input"))?;
+    let layers = [
+        (&options.heights, "heights"),
+        (&options.visibility, "visibility"),
+        (&options.scores, "scores"),
+    ];
+    let raster = |layer: &str| match layer {
+        "heights" => Ok(Raster::heights(&input)),
+        "visibility" => Ok(match &viewer_map {
+            Some(viewer_map) => Raster::visibility(&input, viewer_map, options.viewer),
+            None => Raster::visibility(&input, &visibility_map, None),
+        }),
+        "scores" => Ok(Raster::scores(&input, &scenic_map)),
+        _ => Err(anyhow!("unknown layer: {}", layer)),
+    };
 
+    for (path, layer) in layers {
+        if let Some(path) = path {
+            raster(layer)?.write_netpbm(BufWriter::new(File::create(path)?), options.binary)?;
+        }
+    }
+
+    if let Some(layer) = &options.ansi {
+        print!("{}", raster(layer)?.ansi());
+    }
 
     println!("{} trees are visible", visible_trees);
     println!("{} is maximum scenic score", maximum_scenic_score);
 
+    if let (Some(viewer_map), Some((x, y))) = (&viewer_map, options.viewer) {
+        println!(
+            "{} trees are visible from tree at {},{}",
+            viewer_map.count(),
+            x,
+            y
+        );
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matches_reference(input: &str) {
+        let map = TreeMap::parse(input, Alphabet::Base36).unwrap();
+        let occlusion_map = OcclusionMap::build(&map);
+        let visibility_map = VisibilityMap::from_outside(&map, &AXES);
+        let scenic_map = ScenicMap::build(&map, &AXES);
+
+        for y in 0..map.height {
+            for x in 0..map.width {
+                assert_eq!(
+                    scenic_map.score(&(x, y)),
+                    map.scenic_score((x, y)),
+                    "scenic score of {},{}",
+                    x,
+                    y
+                );
+                assert_eq!(
+                    visibility_map.is_visible(&(x, y)),
+                    occlusion_map.is_visible(&map, &(x, y)),
+                    "visibility of {},{}",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn example_matches_reference() {
+        assert_matches_reference(include_str!("../input_ex"));
+    }
+
+    #[test]
+    fn uneven_heights_match_reference() {
+        assert_matches_reference("z09a3\n1y1y1\n00000\n5k5k9\nb2c3d\n0z0z0\n");
+    }
+}
@@ -0,0 +1,85 @@
+use crate::TreeMap;
+
+/// Trees that can be seen, either from outside of the grid or from a single tree.
+pub struct VisibilityMap(Vec<Vec<bool>>);
+
+impl VisibilityMap {
+    /// Trees seen from outside of the grid when looking against any of `directions`.
+    pub fn from_outside(map: &TreeMap, directions: &[(isize, isize)]) -> Self {
+        let mut result = vec![vec![false; map.width]; map.height];
+
+        for &(dx, dy) in directions {
+            for (mut x, mut y) in map.line_starts((dx, dy)) {
+                let mut tallest = None;
+
+                while map.contains((x, y)) {
+                    let length = map.trees[y as usize][x as usize];
+                    if tallest.is_none_or(|tallest| tallest < length) {
+                        result[y as usize][x as usize] = true;
+                        tallest = Some(length);
+                    }
+
+                    x -= dx;
+                    y -= dy;
+                }
+            }
+        }
+
+        Self(result)
+    }
+
+    /// Trees seen by a viewer standing on the tree at `viewer`. A tree is hidden when a tree at
+    /// least as tall as the viewer's stands on the line between them.
+    pub fn from_viewer(map: &TreeMap, viewer: (usize, usize)) -> Self {
+        let length = map.trees[viewer.1][viewer.0];
+        let mut result = vec![vec![false; map.width]; map.height];
+
+        for (y, row) in result.iter_mut().enumerate() {
+            for (x, visible) in row.iter_mut().enumerate() {
+                let line = bresenham(viewer, (x, y));
+                *visible = line.len() > 1
+                    && line[1..line.len() - 1]
+                        .iter()
+                        .all(|&(x, y)| map.trees[y][x] < length);
+            }
+        }
+
+        Self(result)
+    }
+
+    pub fn is_visible(&self, &(x, y): &(usize, usize)) -> bool {
+        self.0[y][x]
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.iter().flatten().filter(|visible| **visible).count()
+    }
+}
+
+/// Cells on the rasterized line from `from` to `to`, both ends included.
+fn bresenham(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (x1, y1) = (to.0 as isize, to.1 as isize);
+
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut error = dx + dy;
+    let mut result = vec![];
+
+    loop {
+        result.push((x as usize, y as usize));
+        if (x, y) == (x1, y1) {
+            return result;
+        }
+
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
@@ -1,172 +1,363 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::{
-    cmp::Ordering,
-    collections::HashSet,
-    io::{stdin, BufRead, BufReader},
+    fmt,
+    fs::File,
+    io::{stdin, stdout, BufRead, BufReader, BufWriter, Write},
+    ops::RangeInclusive,
     str::FromStr,
 };
 
-#[derive(Debug)]
+mod render;
+mod rope;
+
+use render::Bounds;
+use rope::Rope;
+
+#[derive(Debug, Clone, Copy)]
 enum Direction {
     Left,
     Right,
     Up,
     Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+    Forward,
+    Backward,
+    /// Arbitrary displacement, walked diagonally first and straight for the rest.
+    Vector(isize, isize, isize),
 }
 
 #[derive(Debug)]
 struct Move(Direction, usize);
 
+impl Direction {
+    fn delta(&self) -> (isize, isize, isize) {
+        use Direction::*;
+
+        match self {
+            Left => (-1, 0, 0),
+            Right => (1, 0, 0),
+            Up => (0, -1, 0),
+            Down => (0, 1, 0),
+            UpLeft => (-1, -1, 0),
+            UpRight => (1, -1, 0),
+            DownLeft => (-1, 1, 0),
+            DownRight => (1, 1, 0),
+            Forward => (0, 0, 1),
+            Backward => (0, 0, -1),
+            Vector(dx, dy, dz) => (*dx, *dy, *dz),
+        }
+    }
+}
+
+impl Move {
+    /// Unit steps of the head, each at most one cell along every axis.
+    fn steps(&self) -> impl Iterator<Item = (isize, isize, isize)> {
+        let (dx, dy, dz) = self.0.delta();
+        let length = dx.abs().max(dy.abs()).max(dz.abs());
+        let unit = |d: isize, done: isize| (d.abs() > done) as isize * d.signum();
+
+        (0..self.1).flat_map(move |_| {
+            (0..length).map(move |done| (unit(dx, done), unit(dy, done), unit(dz, done)))
+        })
+    }
+}
+
 impl FromStr for Direction {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use anyhow::anyhow;
-
         Ok(match s {
             "U" => Direction::Up,
             "D" => Direction::Down,
             "L" => Direction::Left,
             "R" => Direction::Right,
+            "UL" => Direction::UpLeft,
+            "UR" => Direction::UpRight,
+            "DL" => Direction::DownLeft,
+            "DR" => Direction::DownRight,
+            "F" => Direction::Forward,
+            "B" => Direction::Backward,
             _ => Err(anyhow!("failed to parse direction: {}", s))?,
         })
     }
 }
 
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.0 {
+            Direction::Up => "U",
+            Direction::Down => "D",
+            Direction::Left => "L",
+            Direction::Right => "R",
+            Direction::UpLeft => "UL",
+            Direction::UpRight => "UR",
+            Direction::DownLeft => "DL",
+            Direction::DownRight => "DR",
+            Direction::Forward => "F",
+            Direction::Backward => "B",
+            Direction::Vector(dx, dy, 0) => return write!(f, "V {} {}", dx, dy),
+            Direction::Vector(dx, dy, dz) => return write!(f, "V {} {} {}", dx, dy, dz),
+        };
+
+        write!(f, "{} {}", direction, self.1)
+    }
+}
+
+fn number<T>(value: &str, what: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| anyhow!("invalid {} {:?}: {}", what, value, e))
+}
+
+/// Vector component, small enough for its length to be representable.
+fn component(value: &str, what: &str) -> Result<isize> {
+    let component = number::<isize>(value, what)?;
+    component
+        .checked_abs()
+        .map(|_| component)
+        .ok_or_else(|| anyhow!("{} {} is out of range", what, value))
+}
+
 impl FromStr for Move {
     type Err = anyhow::Error;
 
+    /// Parses `DIRECTION COUNT` or `V DX DY [DZ]`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(s[0..1].parse()?, s[2..].parse()?))
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["V", dx, dy] => Ok(Self(
+                Direction::Vector(component(dx, "dx")?, component(dy, "dy")?, 0),
+                1,
+            )),
+            ["V", dx, dy, dz] => Ok(Self(
+                Direction::Vector(
+                    component(dx, "dx")?,
+                    component(dy, "dy")?,
+                    component(dz, "dz")?,
+                ),
+                1,
+            )),
+            ["V", ..] => Err(anyhow!("expected V DX DY [DZ], got {:?}", s)),
+            [direction, count] => Ok(Self(direction.parse()?, number(count, "count")?)),
+            [] => Err(anyhow!("line is empty")),
+            _ => Err(anyhow!(
+                "expected DIRECTION COUNT or V DX DY [DZ], got {:?}",
+                s
+            )),
+        }
     }
 }
 
 fn read(reader: impl BufRead) -> impl Iterator<Item = Result<Move>> {
-    use anyhow::anyhow;
-
-    reader.lines().map(|line| {
+    reader.lines().enumerate().map(|(idx, line)| {
         line.map_err(Into::into)
             .and_then(|line| line.parse())
-            .map_err(|e| anyhow!("failed to parse line: {}", e))
+            .map_err(|e| anyhow!("failed to parse line {}: {}", idx + 1, e))
     })
 }
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
-struct Point {
-    x: isize,
-    y: isize,
+/// Rope to simulate and the knot whose positions are counted.
+struct Config {
+    knots: usize,
+    slack: usize,
+    tracked: usize,
 }
 
-struct Rope {
-    head: Point,
-    tails: Vec<Point>,
-}
+impl FromStr for Config {
+    type Err = anyhow::Error;
+
+    /// Parses `KNOTS[,SLACK[,KNOT]]`, tracking the last knot by default.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        let (knots, slack, tracked) = match values[..] {
+            [knots] => (knots, 1, knots.saturating_sub(1)),
+            [knots, slack] => (knots, slack, knots.saturating_sub(1)),
+            [knots, slack, tracked] => (knots, slack, tracked),
+            _ => return Err(anyhow!("expected KNOTS[,SLACK[,KNOT]]: {}", s)),
+        };
+
+        if knots == 0 {
+            return Err(anyhow!("rope needs at least one knot"));
+        }
 
-impl Rope {
-    fn new(tail_count: usize) -> Self {
-        Self {
-            head: Point { x: 0, y: 0 },
-            tails: vec![Point { x: 0, y: 0 }; tail_count],
+        if tracked >= knots {
+            return Err(anyhow!(
+                "knot {} is not in a rope of {} knots",
+                tracked,
+                knots
+            ));
         }
+
+        Ok(Self {
+            knots,
+            slack,
+            tracked,
+        })
     }
+}
 
-    fn step(&mut self, direction: &Direction) {
-        use Direction::*;
+/// How often the rope is drawn.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Frames {
+    Move,
+    Step,
+}
 
-        match direction {
-            Left => {
-                self.head.x -= 1;
-            }
-            Right => {
-                self.head.x += 1;
+#[derive(Default)]
+struct Options {
+    configs: Vec<Config>,
+    frames: Option<Frames>,
+    selected: Option<RangeInclusive<usize>>,
+    svg: Option<String>,
+}
+
+impl Options {
+    fn from_args() -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rope" => {
+                    let config = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--rope expects KNOTS[,SLACK[,KNOT]]"))?;
+                    options.configs.push(config.parse()?);
+                }
+                "--frames" => {
+                    options.frames = match args.next().as_deref() {
+                        Some("move") => Some(Frames::Move),
+                        Some("step") => Some(Frames::Step),
+                        _ => return Err(anyhow!("--frames expects move or step")),
+                    };
+                }
+                "--moves" => {
+                    let range = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--moves expects a range FIRST-LAST"))?;
+                    let (first, last) = range.split_once('-').unwrap_or((&range, &range));
+                    options.selected = Some(first.parse()?..=last.parse()?);
+                }
+                "--svg" => {
+                    options.svg = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--svg expects a file name"))?,
+                    );
+                }
+                _ => return Err(anyhow!("unknown argument: {}", arg)),
             }
-            Up => {
-                self.head.y -= 1;
+        }
+
+        Ok(options)
+    }
+}
+
+fn unique_positions(moves: &[Move], config: &Config) -> Result<usize> {
+    Rope::new(config.knots, config.slack)
+        .visited(moves, config.tracked)
+        .map(|visited| visited.len())
+        .ok_or_else(|| anyhow!("knot {} is not in the rope", config.tracked))
+}
+
+/// Draws the first configured rope, or the ten knot one from the puzzle.
+fn render(moves: &[Move], options: &Options) -> Result<()> {
+    let config = match options.configs.first() {
+        Some(config) => config,
+        None => &"10".parse()?,
+    };
+    let rope = Rope::new(config.knots, config.slack);
+
+    let paths = render::trace(rope.clone(), moves);
+    let bounds = Bounds::new(paths.iter().flatten());
+
+    if let Some(frames) = options.frames {
+        let selected = options.selected.clone().unwrap_or(1..=moves.len());
+
+        // Frames only need to fit the rope during selected moves. Trace has the starting
+        // position and then one entry per step.
+        let mut first_step = 1;
+        let mut shown = 0..0;
+        for (number, move_cmd) in moves.iter().enumerate() {
+            let last_step = first_step + move_cmd.steps().count();
+            if number + 1 == *selected.start() {
+                shown.start = first_step - 1;
             }
-            Down => {
-                self.head.y += 1;
+            if selected.contains(&(number + 1)) {
+                shown.end = last_step;
             }
+            first_step = last_step;
         }
+        let frame_bounds = Bounds::new(paths.iter().flat_map(|path| &path[shown.clone()]));
 
-        self.adjust_tails()
+        render::frames(
+            BufWriter::new(stdout().lock()),
+            rope,
+            moves,
+            &selected,
+            frames == Frames::Step,
+            &frame_bounds,
+        )?;
     }
 
-    fn adjust_tails(&mut self) {
-        self.tails.iter_mut().fold(&self.head, |head, tail| {
-            tail.adjust(head);
-            tail
-        });
+    if let Some(path) = &options.svg {
+        File::create(path)?.write_all(render::svg(&paths, &bounds).as_bytes())?;
     }
 
-    fn final_tail(&self) -> Option<Point> {
-        self.tails.last().copied()
-    }
+    Ok(())
 }
 
-impl Point {
-    fn adjustment(&self, head: &Self) -> (isize, isize) {
-        let (tx, ty) = (self.x, self.y);
-        let (hx, hy) = (head.x, head.y);
-
-        (
-            match tx.cmp(&hx) {
-                Ordering::Equal => 0,
-                Ordering::Greater => -1,
-                _ => 1,
-            },
-            match ty.cmp(&hy) {
-                Ordering::Equal => 0,
-                Ordering::Greater => -1,
-                _ => 1,
-            },
-        )
+fn main() -> Result<()> {
+    let options = Options::from_args()?;
+    let moves = read(BufReader::new(stdin())).collect::<Result<Vec<_>>>()?;
+
+    if options.frames.is_some() || options.svg.is_some() {
+        render(&moves, &options)?;
     }
 
-    fn adjust(&mut self, head: &Self) {
-        let needs_adjustment = ![1, 0, -1]
-            .into_iter()
-            .flat_map(|x| [1, 0, -1].into_iter().map(move |y| (x, y)))
-            .any(|(dx, dy)| head.x + dx == self.x && head.y + dy == self.y);
+    if options.configs.is_empty() {
+        let small = unique_positions(&moves, &"2".parse()?)?;
+        let bigger = unique_positions(&moves, &"10".parse()?)?;
 
-        if needs_adjustment {
-            let (adjx, adjy) = self.adjustment(head);
+        println!("In small rope, knot tail was in {} unique positions", small);
+        println!(
+            "In bigger rope, knot tail was in {} unique positions",
+            bigger
+        );
 
-            self.x += adjx;
-            self.y += adjy;
-        }
+        return Ok(());
     }
-}
 
-fn main() -> Result<()> {
-    let mut set = HashSet::from([Point { x: 0, y: 0 }]);
-    let mut bigger_set: HashSet<Point> = HashSet::from([Point { x: 0, y: 0 }]);
-    let mut rope = Rope::new(1);
-    let mut bigger_rope = Rope::new(9);
-
-    for move_cmd in read(BufReader::new(stdin())) {
-        let Move(direction, step) = move_cmd?;
-
-        (0..step).for_each(|_| {
-            rope.step(&direction);
-            rope.final_tail().into_iter().for_each(|tail| {
-                set.insert(tail);
-            });
-
-            bigger_rope.step(&direction);
-            bigger_rope.final_tail().into_iter().for_each(|tail| {
-                bigger_set.insert(tail);
-            })
-        })
-    }
+    let positions = options
+        .configs
+        .iter()
+        .map(|config| unique_positions(&moves, config))
+        .collect::<Result<Vec<_>>>()?;
 
-    println!(
-        "In small rope, knot tail was in {} unique positions",
-        set.len()
-    );
-    println!(
-        "In bigger rope, knot tail was in {} unique positions",
-        bigger_set.len()
-    );
+    let rows = [
+        ("knots", options.configs.iter().map(|c| c.knots).collect()),
+        ("slack", options.configs.iter().map(|c| c.slack).collect()),
+        ("knot", options.configs.iter().map(|c| c.tracked).collect()),
+        ("positions", positions),
+    ];
+
+    for (label, values) in rows {
+        let cells = values
+            .iter()
+            .map(|value| format!("{:>10}", value))
+            .collect::<Vec<_>>();
+
+        println!("{:<10}{}", label, cells.concat());
+    }
 
     Ok(())
 }
@@ -0,0 +1,176 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    ops::RangeInclusive,
+};
+
+use crate::{
+    rope::{Point, Rope},
+    Move,
+};
+
+/// Smallest box containing every point.
+#[derive(Debug, Clone, Copy)]
+pub struct Bounds {
+    min: Point,
+    max: Point,
+}
+
+impl Bounds {
+    pub fn new<'p>(points: impl IntoIterator<Item = &'p Point>) -> Self {
+        points.into_iter().fold(
+            Self {
+                min: Point::default(),
+                max: Point::default(),
+            },
+            |bounds, pt| Self {
+                min: Point {
+                    x: bounds.min.x.min(pt.x),
+                    y: bounds.min.y.min(pt.y),
+                    z: bounds.min.z.min(pt.z),
+                },
+                max: Point {
+                    x: bounds.max.x.max(pt.x),
+                    y: bounds.max.y.max(pt.y),
+                    z: bounds.max.z.max(pt.z),
+                },
+            },
+        )
+    }
+
+    fn columns(&self) -> RangeInclusive<isize> {
+        self.min.x..=self.max.x
+    }
+
+    fn rows(&self) -> RangeInclusive<isize> {
+        self.min.y..=self.max.y
+    }
+}
+
+/// Positions of every knot, one entry for the start and one per step.
+pub fn trace(mut rope: Rope, moves: &[Move]) -> Vec<Vec<Point>> {
+    let mut paths = rope
+        .knots()
+        .iter()
+        .map(|knot| vec![*knot])
+        .collect::<Vec<_>>();
+
+    for delta in moves.iter().flat_map(Move::steps) {
+        rope.step(delta);
+        for (path, knot) in paths.iter_mut().zip(rope.knots()) {
+            path.push(*knot);
+        }
+    }
+
+    paths
+}
+
+/// Label of a knot as used in the puzzle text.
+fn label(idx: usize, knots: usize) -> char {
+    match idx {
+        0 => 'H',
+        _ if idx + 1 == knots && (knots == 2 || idx > 9) => 'T',
+        // PANIC: Indices up to 9 are single digits.
+        1..=9 => char::from_digit(idx as u32, 10).unwrap(),
+        _ => '+',
+    }
+}
+
+/// Draws the rope like the puzzle text, projected onto the x-y plane. Knots closer to the head
+/// cover the ones behind them and `s` marks the starting position.
+pub fn frame(rope: &Rope, bounds: &Bounds) -> String {
+    let knots = rope.knots();
+    let mut result = String::new();
+
+    for y in bounds.rows() {
+        for x in bounds.columns() {
+            let cell = match knots.iter().position(|knot| (knot.x, knot.y) == (x, y)) {
+                Some(idx) => label(idx, knots.len()),
+                None if (x, y) == (0, 0) => 's',
+                None => '.',
+            };
+            result.push(cell);
+        }
+        result.push('\n');
+    }
+
+    result
+}
+
+/// Writes frames after the selected moves, or after every step of them with `steps`, as soon
+/// as each of them is drawn.
+pub fn frames(
+    mut output: impl Write,
+    mut rope: Rope,
+    moves: &[Move],
+    selected: &RangeInclusive<usize>,
+    steps: bool,
+    bounds: &Bounds,
+) -> io::Result<()> {
+    for (number, move_cmd) in moves.iter().enumerate() {
+        let shown = selected.contains(&(number + 1));
+        if shown {
+            writeln!(output, "== {} ==\n", move_cmd)?;
+        }
+
+        for delta in move_cmd.steps() {
+            rope.step(delta);
+            if shown && steps {
+                writeln!(output, "{}", frame(&rope, bounds))?;
+            }
+        }
+
+        if shown && !steps {
+            writeln!(output, "{}", frame(&rope, bounds))?;
+        }
+    }
+
+    output.flush()
+}
+
+/// One polyline per knot projected onto the x-y plane, head drawn last so it stays on top.
+pub fn svg(paths: &[Vec<Point>], bounds: &Bounds) -> String {
+    const CELL: isize = 10;
+
+    let (width, height) = (
+        (bounds.max.x - bounds.min.x + 1) * CELL,
+        (bounds.max.y - bounds.min.y + 1) * CELL,
+    );
+
+    let mut result = String::new();
+    // PANIC: Writing to a String never fails.
+    writeln!(
+        result,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    )
+    .unwrap();
+    writeln!(result, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+    for (idx, path) in paths.iter().enumerate().rev() {
+        let mut points = path
+            .iter()
+            .map(|pt| {
+                format!(
+                    "{},{}",
+                    (pt.x - bounds.min.x) * CELL + CELL / 2,
+                    (pt.y - bounds.min.y) * CELL + CELL / 2
+                )
+            })
+            .collect::<Vec<_>>();
+        points.dedup();
+        let hue = 360 * idx / paths.len();
+
+        writeln!(
+            result,
+            r#"<polyline id="knot-{}" fill="none" stroke="hsl({}, 80%, 45%)" stroke-width="2" stroke-linejoin="round" points="{}"/>"#,
+            idx,
+            hue,
+            points.join(" ")
+        )
+        .unwrap();
+    }
+
+    result.push_str("</svg>\n");
+    result
+}
@@ -0,0 +1,98 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use crate::Move;
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Default)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+/// Rope made of knots, the first one being the head. Knots may be up to `slack` cells away from
+/// the knot in front of them before they are pulled along.
+#[derive(Debug, Clone)]
+pub struct Rope {
+    knots: Vec<Point>,
+    slack: usize,
+}
+
+impl Rope {
+    pub fn new(knots: usize, slack: usize) -> Self {
+        Self {
+            knots: vec![Point::default(); knots.max(1)],
+            slack,
+        }
+    }
+
+    /// Moves the head by `(dx, dy, dz)` and pulls the other knots after it.
+    pub fn step(&mut self, (dx, dy, dz): (isize, isize, isize)) {
+        let head = &mut self.knots[0];
+        head.x += dx;
+        head.y += dy;
+        head.z += dz;
+
+        self.adjust_tails()
+    }
+
+    fn adjust_tails(&mut self) {
+        for idx in 1..self.knots.len() {
+            let head = self.knots[idx - 1];
+            self.knots[idx].adjust(&head, self.slack);
+        }
+    }
+
+    pub fn knots(&self) -> &[Point] {
+        &self.knots
+    }
+
+    pub fn knot(&self, idx: usize) -> Option<Point> {
+        self.knots.get(idx).copied()
+    }
+
+    /// Positions visited by knot `idx` while the rope performs `moves`.
+    pub fn visited(&mut self, moves: &[Move], idx: usize) -> Option<HashSet<Point>> {
+        let mut visited = HashSet::from([self.knot(idx)?]);
+
+        for delta in moves.iter().flat_map(Move::steps) {
+            self.step(delta);
+            visited.extend(self.knot(idx));
+        }
+
+        Some(visited)
+    }
+}
+
+impl Point {
+    fn adjustment(&self, head: &Self) -> (isize, isize, isize) {
+        let towards = |tail: isize, head: isize| match tail.cmp(&head) {
+            Ordering::Equal => 0,
+            Ordering::Greater => -1,
+            _ => 1,
+        };
+
+        (
+            towards(self.x, head.x),
+            towards(self.y, head.y),
+            towards(self.z, head.z),
+        )
+    }
+
+    /// Chebyshev distance, so points touching diagonally (even in space) are 1 apart.
+    fn distance(&self, other: &Self) -> usize {
+        self.x
+            .abs_diff(other.x)
+            .max(self.y.abs_diff(other.y))
+            .max(self.z.abs_diff(other.z))
+    }
+
+    fn adjust(&mut self, head: &Self, slack: usize) {
+        while self.distance(head) > slack {
+            let (adjx, adjy, adjz) = self.adjustment(head);
+
+            self.x += adjx;
+            self.y += adjy;
+            self.z += adjz;
+        }
+    }
+}